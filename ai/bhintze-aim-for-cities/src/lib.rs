@@ -0,0 +1,114 @@
+use itertools::Itertools;
+use model::{pathfinding::shortest_path, Space, TurnRequest, TurnResponse};
+use serde::{Deserialize, Serialize};
+
+/// How much the bot wants to take each kind of space. It always heads for the space with the
+/// highest priority; spaces it already owns and swamps have priority 0, and mountains -1.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Params {
+    pub enemy_capital: f64,
+    pub enemy_town: f64,
+    pub neutral_town: f64,
+    pub enemy_land: f64,
+    pub empty_land: f64,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params {
+            enemy_capital: 5.0,
+            enemy_town: 4.0,
+            neutral_town: 3.0,
+            enemy_land: 2.0,
+            empty_land: 1.0,
+        }
+    }
+}
+
+impl Params {
+    pub fn to_vec(&self) -> Vec<f64> {
+        vec![
+            self.enemy_capital,
+            self.enemy_town,
+            self.neutral_town,
+            self.enemy_land,
+            self.empty_land,
+        ]
+    }
+
+    /**
+     * The inverse of `to_vec`. Returns `None` if `values` is the wrong length.
+     */
+    pub fn from_slice(values: &[f64]) -> Option<Self> {
+        let [enemy_capital, enemy_town, neutral_town, enemy_land, empty_land] =
+            <[f64; 5]>::try_from(values).ok()?;
+        Some(Params {
+            enemy_capital,
+            enemy_town,
+            neutral_town,
+            enemy_land,
+            empty_land,
+        })
+    }
+}
+
+/**
+ * Moves the biggest army one step towards the highest priority space
+ */
+pub fn choose_move(body: &TurnRequest, params: &Params) -> Option<TurnResponse> {
+    let strengths_of_my_spaces: Vec<_> = body
+        .spaces
+        .owned_by(body.player)
+        .map(|c| (c, body.spaces[c].get_units()))
+        .sorted_by(|lh, rh| rh.1.cmp(&lh.1))
+        .collect();
+    let priorities_of_targets: Vec<_> = body
+        .spaces
+        .iter()
+        .map(|(c, space)| {
+            (
+                c,
+                match space {
+                    Space::NeutralTown { .. } => params.neutral_town,
+                    Space::PlayerTown { owner, .. } => {
+                        if owner == body.player {
+                            0.0
+                        } else {
+                            params.enemy_town
+                        }
+                    }
+
+                    Space::PlayerCapital { owner, units: _ } => {
+                        if owner == body.player {
+                            0.0
+                        } else {
+                            params.enemy_capital
+                        }
+                    }
+
+                    Space::Mountain => -1.0,
+                    Space::Empty | Space::Lookout => params.empty_land,
+                    // Never worth heading for, since it drains whoever holds it
+                    Space::Swamp => 0.0,
+                    Space::PlayerEmpty { owner, units: _ }
+                    | Space::PlayerSwamp { owner, units: _ }
+                    | Space::PlayerLookout { owner, units: _ } => {
+                        if owner == body.player {
+                            0.0
+                        } else {
+                            params.enemy_land
+                        }
+                    }
+                },
+            )
+        })
+        .sorted_by(|lh, rh| rh.1.total_cmp(&lh.1))
+        .collect();
+    let target = priorities_of_targets.first()?;
+    let loc_of_biggest_army = strengths_of_my_spaces.first()?;
+    let path = shortest_path(&body.spaces, loc_of_biggest_army.0, target.0)?;
+    Some(TurnResponse {
+        from: loc_of_biggest_army.0,
+        to: path.next_step()?,
+    })
+}
@@ -1,28 +1,15 @@
 use std::net::{IpAddr, SocketAddr};
 
 use axum::{routing::post, Json, Router};
-use model::{Coordinate, Space, TurnRequest, TurnResponse, BOARD_SIZE};
+use model::{analysis::legal_moves, TurnRequest, TurnResponse};
 use rand::prelude::*;
 
 async fn turn_handler(Json(body): Json<TurnRequest>) -> Json<Option<TurnResponse>> {
-    let mut my_spaces = vec![];
-    for x in 0..BOARD_SIZE {
-        for y in 0..BOARD_SIZE {
-            if body.spaces[x][y].owner() == Some(body.player) && body.spaces[x][y].get_units() > 0 {
-                my_spaces.push(Coordinate { x, y });
-            }
-        }
-    }
-    Json(my_spaces.into_iter().choose(&mut thread_rng()).map(|from| {
-        let to = from
-            .surrounding()
+    Json(
+        legal_moves(&body.spaces, body.player)
             .into_iter()
-            .filter(|to| body.spaces[to.x][to.y] != Space::Mountain)
-            .choose(&mut thread_rng())
-            .expect("Should always be a path out of a space");
-
-        TurnResponse { from, to }
-    }))
+            .choose(&mut thread_rng()),
+    )
 }
 
 #[tokio::main]
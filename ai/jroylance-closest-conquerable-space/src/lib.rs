@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use model::{
+    analysis::{frontier, legal_moves},
+    pathfinding::{shortest_path, Path},
+    Coordinate, Space, TurnRequest, TurnResponse,
+};
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub type DistanceCache = HashMap<(Coordinate, Coordinate), Option<Path>>;
+
+/// The cost of heading for each kind of space; the bot attacks whichever conquerable space has
+/// the lowest cost. The defaults are spread far enough apart that the kind of space always matters
+/// more than the distance to it.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Params {
+    pub capital: f64,
+    pub town: f64,
+    pub neutral_town: f64,
+    pub enemy_land: f64,
+    pub empty_land: f64,
+    /// Added to the cost for every move needed to reach the space
+    pub distance: f64,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Params {
+            capital: 100.0,
+            town: 200.0,
+            neutral_town: 300.0,
+            enemy_land: 400.0,
+            empty_land: 500.0,
+            distance: 1.0,
+        }
+    }
+}
+
+impl Params {
+    pub fn to_vec(&self) -> Vec<f64> {
+        vec![
+            self.capital,
+            self.town,
+            self.neutral_town,
+            self.enemy_land,
+            self.empty_land,
+            self.distance,
+        ]
+    }
+
+    /**
+     * The inverse of `to_vec`. Returns `None` if `values` is the wrong length.
+     */
+    pub fn from_slice(values: &[f64]) -> Option<Self> {
+        let [capital, town, neutral_town, enemy_land, empty_land, distance] =
+            <[f64; 6]>::try_from(values).ok()?;
+        Some(Params {
+            capital,
+            town,
+            neutral_town,
+            enemy_land,
+            empty_land,
+            distance,
+        })
+    }
+}
+
+/**
+ * Moves towards the cheapest space that one of our armies outnumbers, or makes a random move if
+ * there isn't one. `cache` holds paths between spaces, so it should only be reused within a game.
+ */
+pub fn choose_move(
+    body: &TurnRequest,
+    params: &Params,
+    cache: &mut DistanceCache,
+) -> Option<TurnResponse> {
+    let mut my_spaces_with_units = body
+        .spaces
+        .owned_by(body.player)
+        .filter(|c| body.spaces[*c].get_units() > 0)
+        .collect_vec();
+    my_spaces_with_units.shuffle(&mut thread_rng());
+
+    let mut border_spaces = frontier(&body.spaces, body.player);
+    border_spaces.shuffle(&mut thread_rng());
+
+    let mut least_moves: Option<((f64, usize, usize), Coordinate, Coordinate)> = None;
+    for my_space in &my_spaces_with_units {
+        for their_space in &border_spaces {
+            let my_units = body.spaces[*my_space].get_units();
+            let their_units = body.spaces[*their_space].get_units();
+            if my_units > their_units {
+                let Some(path) = cache
+                    .entry((*my_space, *their_space))
+                    .or_insert_with(|| shortest_path(&body.spaces, *my_space, *their_space))
+                else {
+                    continue;
+                };
+
+                let weight = {
+                    let target_priority = match body.spaces[*their_space] {
+                        Space::PlayerCapital { .. } => params.capital,
+                        Space::PlayerTown { .. } => params.town,
+                        Space::NeutralTown { .. } => params.neutral_town,
+                        Space::PlayerEmpty { .. }
+                        | Space::PlayerSwamp { .. }
+                        | Space::PlayerLookout { .. } => params.enemy_land,
+                        Space::Empty | Space::Lookout => params.empty_land,
+                        // Only worth crossing on the way somewhere, since it drains whoever holds it
+                        Space::Swamp => continue,
+                        // The frontier never includes mountains, but skip them rather than panic
+                        Space::Mountain => continue,
+                    };
+                    (
+                        target_priority + params.distance * path.moves() as f64,
+                        usize::MAX - their_units,
+                        usize::MAX - my_units,
+                    )
+                };
+
+                if least_moves.is_none_or(|(least, _, _)| weight < least) {
+                    least_moves = Some((weight, *my_space, path.steps[1]));
+                }
+            }
+        }
+    }
+
+    match least_moves {
+        Some((_, from, to)) => Some(TurnResponse { from, to }),
+        None => legal_moves(&body.spaces, body.player)
+            .into_iter()
+            .choose(&mut thread_rng()),
+    }
+}
@@ -0,0 +1,31 @@
+use model::{analysis::legal_moves, Board, TurnResponse};
+use rand::prelude::*;
+
+/**
+ * Attacks a neighbor we clearly outnumber, otherwise shuffles units around our own territory,
+ * favoring big armies
+ */
+pub fn choose_move<R: Rng + ?Sized>(
+    spaces: &Board,
+    player: usize,
+    rng: &mut R,
+) -> Option<TurnResponse> {
+    let moves = legal_moves(spaces, player);
+    moves
+        .iter()
+        .filter(|m| {
+            spaces[m.to].owner() != Some(player)
+                && spaces[m.to].get_units() + 2 < spaces[m.from].get_units()
+        })
+        .choose(rng)
+        .or_else(|| {
+            moves
+                .iter()
+                .filter(|m| spaces[m.to].owner() == Some(player))
+                .collect::<Vec<_>>()
+                .choose_weighted(rng, |m| spaces[m.from].get_units())
+                .ok()
+                .copied()
+        })
+        .copied()
+}
@@ -0,0 +1,81 @@
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+
+use axum::{extract::State, routing::post, Json, Router};
+use engine::GameState;
+use mcts::{best_move, SearchConfig};
+use model::{TurnRequest, TurnResponse};
+use policy::{policy_from_name, RolloutPolicy};
+
+mod mcts;
+mod policy;
+
+struct Bot {
+    policy: Box<dyn RolloutPolicy>,
+    config: SearchConfig,
+}
+
+async fn turn_handler(
+    State(bot): State<Arc<Bot>>,
+    Json(body): Json<TurnRequest>,
+) -> Json<Option<TurnResponse>> {
+    // The search hogs the CPU for its whole budget, so keep it off the async workers
+    let response = tokio::task::spawn_blocking(move || {
+        let state = GameState::from_turn_request(&body);
+        best_move(&state, body.player, bot.policy.as_ref(), &bot.config)
+    })
+    .await
+    .unwrap_or(None);
+    Json(response)
+}
+
+#[tokio::main]
+async fn main() {
+    let mut args = std::env::args().skip(1);
+    let port: u16 = args
+        .next()
+        .expect("Should pass the port to run on as the first argument")
+        .parse()
+        .expect("First argument should be a valid port");
+
+    let mut bot = Bot {
+        policy: Box::new(policy::RandomPolicy),
+        config: SearchConfig::default(),
+    };
+    while let Some(flag) = args.next() {
+        let value = args
+            .next()
+            .unwrap_or_else(|| panic!("Flag {flag} should be followed by a value"));
+        match flag.as_str() {
+            "--budget-ms" => {
+                bot.config.budget =
+                    Duration::from_millis(value.parse().expect("Budget should be a number"))
+            }
+            "--rollout" => {
+                bot.policy = policy_from_name(&value).unwrap_or_else(|err| panic!("{err}"))
+            }
+            "--rollout-depth" => {
+                bot.config.rollout_depth = value.parse().expect("Rollout depth should be a number")
+            }
+            _ => panic!("Unknown flag {flag}. Expected --budget-ms, --rollout or --rollout-depth"),
+        }
+    }
+
+    let host = std::env::var("HOST_ADDRESS").unwrap_or_else(|_| "127.0.0.1".to_string());
+    let ip: IpAddr = host.parse().expect("Invalid IP address");
+
+    let addr = SocketAddr::from((ip, port));
+    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
+
+    axum::serve(
+        listener,
+        Router::new()
+            .route("/", post(turn_handler))
+            .with_state(Arc::new(bot)),
+    )
+    .await
+    .unwrap();
+}
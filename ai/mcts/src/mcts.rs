@@ -0,0 +1,252 @@
+use std::time::{Duration, Instant};
+
+use engine::{GameState, Move};
+use model::{
+    analysis::{legal_moves, player_totals},
+    TurnResponse,
+};
+use rand::prelude::*;
+
+use crate::policy::RolloutPolicy;
+
+pub struct SearchConfig {
+    /// How long to search for each turn
+    pub budget: Duration,
+    /// How many turns to play out with the rollout policy after leaving the tree
+    pub rollout_depth: usize,
+    /// How deep the tree is allowed to grow, in turns
+    pub max_tree_depth: usize,
+    /// The UCB1 exploration constant
+    pub exploration: f64,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        SearchConfig {
+            budget: Duration::from_millis(100),
+            rollout_depth: 20,
+            max_tree_depth: 10,
+            exploration: std::f64::consts::SQRT_2,
+        }
+    }
+}
+
+/// `None` is passing the turn
+type Action = Option<TurnResponse>;
+
+#[derive(Default)]
+struct Node {
+    visits: u32,
+    total_value: f64,
+    children: Vec<(Action, usize)>,
+    /// Filled in the first time the node is reached, from whichever state was sampled then
+    untried: Option<Vec<Action>>,
+}
+
+/// Open-loop Monte Carlo Tree Search over our own moves. Opponents move simultaneously according to
+/// `policy`, so every iteration re-simulates the line from the root instead of storing states in
+/// the tree.
+pub fn best_move(
+    root: &GameState,
+    player: usize,
+    policy: &dyn RolloutPolicy,
+    config: &SearchConfig,
+) -> Option<TurnResponse> {
+    let deadline = Instant::now() + config.budget;
+    let mut rng = thread_rng();
+    let mut nodes = vec![Node::default()];
+
+    while Instant::now() < deadline {
+        let mut state = root.clone();
+        let mut path = vec![0];
+        let mut node = 0;
+
+        // Selection and expansion
+        while state.winner().is_none() && path.len() <= config.max_tree_depth {
+            if nodes[node].untried.is_none() {
+                let mut actions: Vec<Action> = legal_moves(&state.spaces, player)
+                    .into_iter()
+                    .map(Some)
+                    .collect();
+                actions.push(None);
+                actions.shuffle(&mut rng);
+                nodes[node].untried = Some(actions);
+            }
+
+            if let Some(action) = nodes[node].untried.as_mut().and_then(|u| u.pop()) {
+                let child = nodes.len();
+                nodes.push(Node::default());
+                nodes[node].children.push((action, child));
+                play_turn(&mut state, player, action, policy, &mut rng);
+                path.push(child);
+                break;
+            }
+
+            let parent_visits = f64::from(nodes[node].visits.max(1));
+            let Some(&(action, child)) = nodes[node].children.iter().max_by(|a, b| {
+                let ucb = |n: &Node| {
+                    n.total_value / f64::from(n.visits.max(1))
+                        + config.exploration
+                            * (parent_visits.ln() / f64::from(n.visits.max(1))).sqrt()
+                };
+                ucb(&nodes[a.1]).total_cmp(&ucb(&nodes[b.1]))
+            }) else {
+                break;
+            };
+            play_turn(&mut state, player, action, policy, &mut rng);
+            node = child;
+            path.push(node);
+        }
+
+        // Rollout
+        for _ in 0..config.rollout_depth {
+            if state.winner().is_some() {
+                break;
+            }
+            let action = policy.choose(&state, player, &mut rng);
+            play_turn(&mut state, player, action, policy, &mut rng);
+        }
+
+        // Backpropagation
+        let value = evaluate(&state, player);
+        for node in path {
+            nodes[node].visits += 1;
+            nodes[node].total_value += value;
+        }
+    }
+
+    let best = nodes[0]
+        .children
+        .iter()
+        .max_by_key(|(_, child)| nodes[*child].visits);
+    match best {
+        Some((action, _)) => *action,
+        // Didn't get a single iteration in, so fall back on the policy
+        None => policy.choose(root, player, &mut rng),
+    }
+}
+
+/// Plays one turn where `player` takes `action` and everyone else follows `policy`
+fn play_turn(
+    state: &mut GameState,
+    player: usize,
+    action: Action,
+    policy: &dyn RolloutPolicy,
+    rng: &mut ThreadRng,
+) {
+    let mut responses: Vec<(usize, TurnResponse)> = state
+        .alive_players()
+        .into_iter()
+        .filter(|p| *p != player)
+        .filter_map(|p| Some((p, policy.choose(state, p, rng)?)))
+        .collect();
+    responses.extend(action.map(|a| (player, a)));
+    // Our action was picked on a different sample of the opponents' moves, so it might not be
+    // possible anymore
+    let moves: Vec<Move> = responses
+        .into_iter()
+        .filter_map(|(p, r)| state.validate_move(p, r.from, r.to).ok())
+        .collect();
+    if state.step(moves).is_err() {
+        state.end_turn();
+    }
+}
+
+/// How well `player` is doing, from 0 (eliminated) to 1 (won)
+fn evaluate(state: &GameState, player: usize) -> f64 {
+    if let Some(winner) = state.winner() {
+        return if winner == player { 1.0 } else { 0.0 };
+    }
+    let totals = player_totals(&state.spaces);
+    let Some(mine) = totals.get(&player) else {
+        return 0.0;
+    };
+    let share = |mine: usize, total: usize| {
+        if total == 0 {
+            0.0
+        } else {
+            mine as f64 / total as f64
+        }
+    };
+    let land = share(mine.land, totals.values().map(|t| t.land).sum());
+    let units = share(mine.units, totals.values().map(|t| t.units).sum());
+    let towns = share(mine.towns, totals.values().map(|t| t.towns).sum());
+    (land + units + towns) / 3.0
+}
+
+#[cfg(test)]
+mod tests {
+    use model::{Board, Coordinate, Space};
+
+    use super::*;
+    use crate::policy::RandomPolicy;
+
+    fn c(x: usize, y: usize) -> Coordinate {
+        Coordinate { x, y }
+    }
+
+    fn state_with(spaces: &[(Coordinate, Space)]) -> GameState {
+        let mut board = Board::default();
+        for (at, space) in spaces {
+            board[*at] = *space;
+        }
+        GameState {
+            spaces: board,
+            turn: 1,
+        }
+    }
+
+    #[test]
+    fn takes_the_last_enemy_space() {
+        // The enemy capital is walled in on every side but ours
+        let state = state_with(&[
+            (
+                c(0, 0),
+                Space::PlayerCapital {
+                    owner: 0,
+                    units: 10,
+                },
+            ),
+            (c(1, 0), Space::PlayerCapital { owner: 1, units: 1 }),
+            (c(2, 0), Space::Mountain),
+            (c(1, 1), Space::Mountain),
+        ]);
+        // Without rollouts every iteration is cheap, so even a debug build under load gets enough
+        // of them in to tell the moves apart
+        let config = SearchConfig {
+            budget: Duration::from_millis(50),
+            rollout_depth: 0,
+            ..SearchConfig::default()
+        };
+
+        assert_eq!(
+            best_move(&state, 0, &RandomPolicy, &config),
+            Some(TurnResponse {
+                from: c(0, 0),
+                to: c(1, 0)
+            })
+        );
+    }
+
+    #[test]
+    fn evaluates_shares_of_land_units_and_towns() {
+        let state = state_with(&[
+            (c(0, 0), Space::PlayerCapital { owner: 0, units: 6 }),
+            (c(0, 1), Space::PlayerEmpty { owner: 0, units: 2 }),
+            (c(5, 5), Space::PlayerCapital { owner: 1, units: 8 }),
+        ]);
+
+        // Land 2/3 and 1/3, units and towns split evenly
+        assert!((evaluate(&state, 0) - 5.0 / 9.0).abs() < 1e-9);
+        assert!((evaluate(&state, 1) - 4.0 / 9.0).abs() < 1e-9);
+        assert_eq!(evaluate(&state, 2), 0.0);
+    }
+
+    #[test]
+    fn evaluates_a_win_as_one_and_a_loss_as_zero() {
+        let state = state_with(&[(c(0, 0), Space::PlayerCapital { owner: 0, units: 6 })]);
+
+        assert_eq!(evaluate(&state, 0), 1.0);
+        assert_eq!(evaluate(&state, 1), 0.0);
+    }
+}
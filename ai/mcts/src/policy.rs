@@ -0,0 +1,49 @@
+use engine::GameState;
+use model::{analysis::legal_moves, TurnResponse};
+use rand::prelude::*;
+
+/// Decides what a player does during a rollout, and what the opponents do while the tree is
+/// explored
+pub trait RolloutPolicy: Send + Sync {
+    fn choose(&self, state: &GameState, player: usize, rng: &mut ThreadRng)
+        -> Option<TurnResponse>;
+}
+
+/// Picks uniformly between every legal move
+pub struct RandomPolicy;
+
+impl RolloutPolicy for RandomPolicy {
+    fn choose(
+        &self,
+        state: &GameState,
+        player: usize,
+        rng: &mut ThreadRng,
+    ) -> Option<TurnResponse> {
+        legal_moves(&state.spaces, player).into_iter().choose(rng)
+    }
+}
+
+/// The `jroylance-ai` heuristic: attack a neighbor we clearly outnumber, otherwise shuffle units
+/// around our own territory, favoring big armies
+pub struct JroylancePolicy;
+
+impl RolloutPolicy for JroylancePolicy {
+    fn choose(
+        &self,
+        state: &GameState,
+        player: usize,
+        rng: &mut ThreadRng,
+    ) -> Option<TurnResponse> {
+        jroylance_ai::choose_move(&state.spaces, player, rng)
+    }
+}
+
+pub fn policy_from_name(name: &str) -> Result<Box<dyn RolloutPolicy>, String> {
+    match name {
+        "random" => Ok(Box::new(RandomPolicy)),
+        "jroylance" => Ok(Box::new(JroylancePolicy)),
+        _ => Err(format!(
+            "Unknown rollout policy '{name}'. Expected 'random' or 'jroylance'."
+        )),
+    }
+}
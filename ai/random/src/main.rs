@@ -1,28 +1,15 @@
 use std::net::{IpAddr, SocketAddr};
 
 use axum::{routing::post, Json, Router};
-use model::{Coordinate, Space, TurnRequest, TurnResponse, BOARD_SIZE};
+use model::{analysis::legal_moves, BotRegistration, TurnRequest, TurnResponse, PROTOCOL_VERSION};
 use rand::prelude::*;
 
 async fn turn_handler(Json(body): Json<TurnRequest>) -> Json<Option<TurnResponse>> {
-    let mut my_spaces = vec![];
-    for x in 0..BOARD_SIZE {
-        for y in 0..BOARD_SIZE {
-            if body.spaces[x][y].owner() == Some(body.player) && body.spaces[x][y].get_units() > 0 {
-                my_spaces.push(Coordinate { x, y });
-            }
-        }
-    }
-    Json(my_spaces.into_iter().choose(&mut thread_rng()).map(|from| {
-        let to = from
-            .surrounding()
+    Json(
+        legal_moves(&body.spaces, body.player)
             .into_iter()
-            .filter(|to| body.spaces[to.x][to.y] != Space::Mountain)
-            .choose(&mut thread_rng())
-            .expect("Should always be a path out of a space");
-
-        TurnResponse { from, to }
-    }))
+            .choose(&mut thread_rng()),
+    )
 }
 
 #[tokio::main]
@@ -39,7 +26,47 @@ async fn main() {
     let addr = SocketAddr::from((ip, port));
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
 
+    if let Ok(lobby) = std::env::var("LOBBY_URL") {
+        let endpoint = std::env::var("BOT_ENDPOINT")
+            .unwrap_or_else(|_| listener.local_addr().unwrap().to_string());
+        tokio::spawn(join_lobby(lobby, endpoint));
+    }
+
     axum::serve(listener, Router::new().route("/", post(turn_handler)))
         .await
         .unwrap();
 }
+
+/// Registers with the game server's lobby at `lobby` and waits in its matchmaking queue
+async fn join_lobby(lobby: String, endpoint: String) {
+    let client = reqwest::Client::new();
+    let registered: serde_json::Value = match client
+        .post(format!("{lobby}/lobby/bots"))
+        .json(&BotRegistration {
+            name: "random".to_string(),
+            author: "generals-io-ai".to_string(),
+            endpoint,
+            protocol_version: PROTOCOL_VERSION,
+            color: None,
+        })
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+    {
+        Ok(response) => response.json().await.unwrap_or_default(),
+        Err(err) => {
+            eprintln!("Couldn't register with the lobby: {err}");
+            return;
+        }
+    };
+
+    let queued = client
+        .post(format!("{lobby}/lobby/queue"))
+        .json(&serde_json::json!({ "bot": registered["id"] }))
+        .send()
+        .await
+        .and_then(|response| response.error_for_status());
+    if let Err(err) = queued {
+        eprintln!("Couldn't join the lobby queue: {err}");
+    }
+}
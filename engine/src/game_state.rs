@@ -0,0 +1,998 @@
+use std::{
+    borrow::BorrowMut,
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    error::Error,
+    fmt::{Display, Write},
+};
+
+use model::{Board, Coordinate, Space, SpaceError, TurnRequest, BOARD_SIZE};
+
+use crate::TownConfig;
+use rand::{seq::SliceRandom, thread_rng};
+use serde::{Deserialize, Serialize};
+
+const NUM_TOWNS: usize = 10;
+const NUM_MOUNTAINS: usize = 100;
+const NUM_SWAMPS: usize = 20;
+const NUM_LOOKOUTS: usize = 4;
+const CAPITAL_STARTING_UNITS: usize = 5;
+/// Unowned towns slowly win back their garrison, a unit at a time, but never grow past it
+const NEUTRAL_TOWN_REGENERATION_TURNS: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Move {
+    pub owner: usize,
+    pub units: usize,
+    pub from: Coordinate,
+    pub to: Coordinate,
+}
+
+/// Units of one player arriving at a space
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Army {
+    pub owner: usize,
+    pub units: usize,
+}
+
+/// A space that was moved onto by players who didn't already hold it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Battle {
+    pub at: Coordinate,
+    /// What each attacking player had left when they arrived, after meeting in the middle
+    pub attackers: Vec<Army>,
+    /// Who held the space before, if anyone
+    pub defender: Option<usize>,
+    pub defending_units: usize,
+    /// Whether the space changed hands
+    pub captured: bool,
+}
+
+/// What happened while resolving a turn's moves
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TurnReport {
+    /// Every army that left its space, with the units it actually took
+    pub moves: Vec<Move>,
+    pub battles: Vec<Battle>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GenerationError {
+    NotEnoughSpace { num_players: usize },
+    EmptyGarrisonRange { min: usize, max: usize },
+    NoTownGrowth,
+}
+impl Display for GenerationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GenerationError::NotEnoughSpace { num_players } => write!(
+                f,
+                "A {BOARD_SIZE}x{BOARD_SIZE} board doesn't have room for {num_players} players"
+            ),
+            GenerationError::EmptyGarrisonRange { min, max } => write!(
+                f,
+                "Town garrisons can't be at least {min} and at most {max} at the same time"
+            ),
+            GenerationError::NoTownGrowth => f.write_str("Towns need at least one growth rate"),
+        }
+    }
+}
+impl Error for GenerationError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveError {
+    OutOfBounds,
+    NotAdjacent,
+    NotOwned,
+    IntoMountain,
+    Space(SpaceError),
+}
+impl Display for MoveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MoveError::OutOfBounds => f.write_str("move was out of bounds"),
+            MoveError::NotAdjacent => f.write_str("move was not to an adjacent space"),
+            MoveError::NotOwned => f.write_str("move was from a space they didn't own"),
+            MoveError::IntoMountain => f.write_str("move was onto a mountain"),
+            MoveError::Space(err) => err.fmt(f),
+        }
+    }
+}
+impl Error for MoveError {}
+impl From<SpaceError> for MoveError {
+    fn from(err: SpaceError) -> Self {
+        MoveError::Space(err)
+    }
+}
+
+/// Everything needed to play out the rest of a game. Cloning is a plain copy of the board, so
+/// search-based bots can afford to clone a state for every line they explore.
+#[derive(Debug, Serialize, Clone)]
+pub struct GameState {
+    pub spaces: Board,
+    pub turn: usize,
+}
+impl GameState {
+    pub fn new(num_players: usize, towns: &TownConfig) -> Result<Self, GenerationError> {
+        towns.check()?;
+        let mut spaces = Board::default();
+
+        fn unoccupied_spaces(spaces: &Board) -> Vec<Coordinate> {
+            Board::coordinates()
+                .filter(|c| spaces[*c] == Space::Empty)
+                .collect()
+        }
+        fn random_unoccupied_space(spaces: &Board) -> Option<Coordinate> {
+            unoccupied_spaces(spaces).choose(&mut thread_rng()).copied()
+        }
+        fn still_connected(spaces: &Board) -> bool {
+            let mut visited = [[false; BOARD_SIZE]; BOARD_SIZE];
+
+            let mut visit_queue = VecDeque::new();
+            let Some(start) = Board::coordinates().find(|c| spaces[*c].is_passable()) else {
+                // Nothing left to be disconnected
+                return true;
+            };
+            visited[start.x][start.y] = true;
+            visit_queue.push_back(start);
+
+            while let Some(space) = visit_queue.pop_back() {
+                for next_space in spaces.passable_neighbors(space) {
+                    if !visited[next_space.x][next_space.y] {
+                        visited[next_space.x][next_space.y] = true;
+                        visit_queue.push_back(next_space);
+                    }
+                }
+            }
+
+            Board::coordinates().all(|c| !spaces[c].is_passable() || visited[c.x][c.y])
+        }
+
+        for player_index in 0..num_players {
+            let capital_coord = random_unoccupied_space(&spaces)
+                .ok_or(GenerationError::NotEnoughSpace { num_players })?;
+            spaces[capital_coord] = Space::PlayerCapital {
+                owner: player_index,
+                units: CAPITAL_STARTING_UNITS,
+            };
+        }
+        for _ in 0..NUM_TOWNS {
+            // Towns are a nice-to-have, so a crowded board just gets fewer of them
+            let Some(coord) = random_unoccupied_space(&spaces) else {
+                break;
+            };
+            spaces[coord] = towns.random_town(&mut thread_rng());
+        }
+        // Like towns, swamps and lookouts are left out of a board that's too crowded for them
+        for (terrain, count) in [(Space::Swamp, NUM_SWAMPS), (Space::Lookout, NUM_LOOKOUTS)] {
+            for _ in 0..count {
+                let Some(coord) = random_unoccupied_space(&spaces) else {
+                    break;
+                };
+                spaces[coord] = terrain;
+            }
+        }
+        // Try every unoccupied space at most once, so that a board that can't fit all the
+        // mountains still finishes generating
+        let mut mountain_candidates = unoccupied_spaces(&spaces);
+        mountain_candidates.shuffle(&mut thread_rng());
+        let mut num_mountains_remaining = NUM_MOUNTAINS;
+        for coord in mountain_candidates {
+            if num_mountains_remaining == 0 {
+                break;
+            }
+            spaces[coord] = Space::Mountain;
+            if still_connected(&spaces) {
+                num_mountains_remaining -= 1;
+            } else {
+                spaces[coord] = Space::Empty;
+            }
+        }
+
+        Ok(GameState { spaces, turn: 0 })
+    }
+
+    /// The state a bot was shown in `request`, to simulate forward from
+    pub fn from_turn_request(request: &TurnRequest) -> Self {
+        GameState {
+            spaces: request.spaces,
+            turn: request.turn,
+        }
+    }
+
+    /// Plays out one whole turn: resolves `moves`, grows armies and advances the turn counter. This
+    /// is exactly what the server does every turn. If any move is invalid, the state is left
+    /// untouched.
+    pub fn step(&mut self, moves: Vec<Move>) -> Result<TurnReport, MoveError> {
+        let report = self.handle_moves(moves)?;
+        self.end_turn();
+        Ok(report)
+    }
+
+    /// Grows armies and advances the turn counter, without moving anything
+    pub fn end_turn(&mut self) {
+        self.populate_spaces();
+        self.turn += 1;
+    }
+
+    /// Players that still own at least one space
+    pub fn alive_players(&self) -> BTreeSet<usize> {
+        self.spaces
+            .iter()
+            .filter_map(|(_, space)| space.owner())
+            .collect()
+    }
+
+    /// The last player standing, once everyone else has lost all their spaces
+    pub fn winner(&self) -> Option<usize> {
+        let alive = self.alive_players();
+        if alive.len() == 1 {
+            alive.first().copied()
+        } else {
+            None
+        }
+    }
+
+    /// Takes away everything `player` owns, for players who have left the game. Capitals and towns
+    /// become neutral towns garrisoned by the units they had, and other land is left empty.
+    pub fn neutralize(&mut self, player: usize) {
+        for c in Board::coordinates() {
+            if self.spaces[c].owner() != Some(player) {
+                continue;
+            }
+            self.spaces[c] = match self.spaces[c] {
+                Space::PlayerCapital { units, .. } => Space::NeutralTown {
+                    units,
+                    growth: 1,
+                    garrison: units,
+                },
+                Space::PlayerTown { units, growth, .. } => Space::NeutralTown {
+                    units,
+                    growth,
+                    garrison: units,
+                },
+                Space::PlayerSwamp { .. } => Space::Swamp,
+                Space::PlayerLookout { .. } => Space::Lookout,
+                _ => Space::Empty,
+            };
+        }
+    }
+
+    /// Turns a player's response into a move, checking that it's one they're allowed to make
+    pub fn validate_move(
+        &self,
+        player: usize,
+        from: Coordinate,
+        to: Coordinate,
+    ) -> Result<Move, MoveError> {
+        let m = Move {
+            owner: player,
+            units: if from.is_in_bounds() {
+                self.spaces[from].get_units()
+            } else {
+                0
+            },
+            from,
+            to,
+        };
+        self.check_move(&m)?;
+        Ok(m)
+    }
+
+    fn check_move(&self, m: &Move) -> Result<(), MoveError> {
+        if !m.from.is_in_bounds() || !m.to.is_in_bounds() {
+            Err(MoveError::OutOfBounds)
+        } else if !m.from.is_adjacent_to(&m.to) {
+            Err(MoveError::NotAdjacent)
+        } else if self.spaces[m.from].owner() != Some(m.owner) {
+            Err(MoveError::NotOwned)
+        } else if self.spaces[m.to] == Space::Mountain {
+            Err(MoveError::IntoMountain)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Resolves every move of a turn at once. The outcome never depends on the order of `moves`,
+    /// which is resolved in these phases:
+    ///
+    /// 1. Normalization: moves are sorted into a canonical order and moves sharing the same owner,
+    ///    source and destination are merged. A space can't send out more units than it holds.
+    /// 2. Holding: an army whose space is being moved onto by another player stays to defend it,
+    ///    instead of leaving the space empty for the attacker. An attacker coming the opposite way
+    ///    along the army's own path doesn't count, since the two meet in the middle instead.
+    /// 3. Departure: every other army leaves its source space at the same time. Spaces keep their
+    ///    owner, along with any units that didn't move.
+    /// 4. Meet in the middle: two armies of different owners moving along the same edge in
+    ///    opposite directions fight, and both lose as many units as the smaller one had. Armies of
+    ///    the same owner swap places unharmed.
+    /// 5. Reinforcement: armies moving onto a space their owner already holds join its units, so
+    ///    units passed along a chain of spaces are never lost or duplicated.
+    /// 6. Attack: the remaining armies are grouped by destination, with armies of the same owner
+    ///    joined together. Attackers wear each other down until at most one is left, which then
+    ///    fights the units on the space.
+    ///
+    /// Returns the armies that moved and the battles that were fought. If any move is invalid, the
+    /// state is left untouched.
+    pub fn handle_moves(&mut self, moves: Vec<Move>) -> Result<TurnReport, MoveError> {
+        for m in &moves {
+            self.check_move(m)?;
+        }
+        let moves = Self::normalize_moves(moves);
+
+        // Holding
+        let mut moves: Vec<Move> = moves
+            .iter()
+            .filter(|m| {
+                !moves.iter().any(|attacker| {
+                    attacker.owner != m.owner && attacker.to == m.from && attacker.from != m.to
+                })
+            })
+            .copied()
+            .collect();
+
+        // Departure
+        for m in &mut moves {
+            let available = self.spaces[m.from].get_units();
+            m.units = m.units.min(available);
+            self.spaces[m.from].set_units(available - m.units)?;
+        }
+        moves.retain(|m| m.units > 0);
+        let mut report = TurnReport {
+            moves: moves.clone(),
+            battles: vec![],
+        };
+
+        // Meet in the middle. Each directed edge carries at most one army after normalization, so
+        // fights only ever involve one pair of armies.
+        let units_before_meeting: Vec<usize> = moves.iter().map(|m| m.units).collect();
+        for i in 0..moves.len() {
+            if let Some(j) = moves.iter().position(|other| {
+                other.owner != moves[i].owner
+                    && other.from == moves[i].to
+                    && other.to == moves[i].from
+            }) {
+                moves[i].units -= units_before_meeting[i].min(units_before_meeting[j]);
+            }
+        }
+        moves.retain(|m| m.units > 0);
+
+        // Reinforcement
+        for m in &mut moves {
+            if self.spaces[m.to].owner() == Some(m.owner) {
+                let units = self.spaces[m.to].get_units() + m.units;
+                self.spaces[m.to].set_units(units)?;
+                m.units = 0;
+            }
+        }
+        moves.retain(|m| m.units > 0);
+
+        // Create mapping from destination to owner to units
+        let mut attacks: BTreeMap<Coordinate, BTreeMap<usize, usize>> = BTreeMap::new();
+        for m in moves {
+            *attacks.entry(m.to).or_default().entry(m.owner).or_default() += m.units;
+        }
+
+        for (dest, armies) in attacks {
+            let mut armies: Vec<(usize, usize)> = armies.into_iter().collect();
+            let mut battle = Battle {
+                at: dest,
+                attackers: armies
+                    .iter()
+                    .map(|&(owner, units)| Army { owner, units })
+                    .collect(),
+                defender: self.spaces[dest].owner(),
+                defending_units: self.spaces[dest].get_units(),
+                captured: false,
+            };
+            // Progressively eliminate attacking armies by subtracting the total units of the weakest attacker, and removing armies with 0 units
+            while armies.len() > 1 {
+                let weakest_army_units = armies
+                    .iter()
+                    .map(|(_, units)| *units)
+                    .min()
+                    .expect("Just checked that length > 1");
+
+                for (_, units) in armies.iter_mut() {
+                    *units -= weakest_army_units;
+                }
+
+                armies.retain(|(_, units)| *units > 0);
+            }
+            // Only need to worry about the case where there's one person moving to the space
+            // If 0, we don't do anything.
+            if let Some((owner, source_units)) = armies.first() {
+                let defending_units = self.spaces[dest].get_units();
+
+                if defending_units < *source_units {
+                    let remaining_units = source_units - (defending_units);
+                    // Attacker wins
+                    self.spaces[dest] = self.spaces[dest].captured_by(*owner, remaining_units)?;
+                    battle.captured = true;
+                } else {
+                    // Defender wins
+                    if !matches!(
+                        self.spaces[dest],
+                        Space::Empty | Space::Swamp | Space::Lookout
+                    ) {
+                        self.spaces[dest]
+                            .set_units(defending_units.saturating_sub(*source_units))?;
+                    }
+                }
+            }
+            report.battles.push(battle);
+        }
+        Ok(report)
+    }
+
+    /// Sorts moves into a canonical order and merges the ones that share an owner, source and
+    /// destination, so that every directed edge carries at most one army.
+    fn normalize_moves(moves: Vec<Move>) -> Vec<Move> {
+        let mut merged: BTreeMap<(Coordinate, Coordinate, usize), usize> = BTreeMap::new();
+        for m in moves {
+            *merged.entry((m.from, m.to, m.owner)).or_default() += m.units;
+        }
+        merged
+            .into_iter()
+            .map(|((from, to, owner), units)| Move {
+                owner,
+                units,
+                from,
+                to,
+            })
+            .collect()
+    }
+
+    /// Grows armies, regenerates neutral towns and drains swamps. A swamp that runs out of units is
+    /// no longer owned.
+    pub fn populate_spaces(&mut self) {
+        for c in Board::coordinates() {
+            match self.spaces[c].borrow_mut() {
+                Space::PlayerCapital { owner: _, units } => *units += 1,
+                Space::PlayerTown { units, growth, .. } if self.turn.is_multiple_of(2) => {
+                    *units += *growth
+                }
+                Space::NeutralTown {
+                    units, garrison, ..
+                } if self.turn.is_multiple_of(NEUTRAL_TOWN_REGENERATION_TURNS)
+                    && *units < *garrison =>
+                {
+                    *units += 1
+                }
+                Space::PlayerEmpty { owner: _, units } if self.turn.is_multiple_of(25) => {
+                    *units += 1
+                }
+                Space::PlayerSwamp { owner: _, units } => *units = units.saturating_sub(1),
+                _ => {}
+            }
+            if let Space::PlayerSwamp { units: 0, .. } = self.spaces[c] {
+                self.spaces[c] = Space::Swamp;
+            }
+        }
+    }
+}
+
+impl Display for GameState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for y in 0..BOARD_SIZE {
+            for x in 0..BOARD_SIZE {
+                let char = match self.spaces[Coordinate { x, y }] {
+                    Space::PlayerCapital { .. } => 'P',
+                    Space::PlayerTown { .. } => 'p',
+                    Space::NeutralTown { .. } => 'n',
+                    Space::PlayerEmpty { .. } => 'p',
+                    Space::Empty => ' ',
+                    Space::Mountain => '^',
+                    Space::PlayerSwamp { .. } => 'p',
+                    Space::Swamp => '~',
+                    Space::PlayerLookout { .. } => 'p',
+                    Space::Lookout => 'o',
+                };
+                f.write_char(char)?;
+            }
+            f.write_char('\n')?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+
+    use super::*;
+
+    fn c(x: usize, y: usize) -> Coordinate {
+        Coordinate { x, y }
+    }
+
+    fn state_with(spaces: &[(Coordinate, Space)]) -> GameState {
+        let mut state = GameState {
+            spaces: Board::default(),
+            turn: 1,
+        };
+        for (coord, space) in spaces {
+            state.spaces[*coord] = *space;
+        }
+        state
+    }
+
+    fn mv(owner: usize, units: usize, from: Coordinate, to: Coordinate) -> Move {
+        Move {
+            owner,
+            units,
+            from,
+            to,
+        }
+    }
+
+    /// Resolves `moves` in every possible order and checks that all of them agree
+    fn resolve_in_every_order(state: &GameState, moves: &[Move]) -> GameState {
+        let mut results = moves
+            .iter()
+            .copied()
+            .permutations(moves.len())
+            .map(|moves| {
+                let mut state = state.clone();
+                state.handle_moves(moves).expect("Moves are valid");
+                state
+            });
+        let first = results
+            .next()
+            .expect("There's always at least one ordering");
+        for other in results {
+            assert_eq!(first.spaces, other.spaces);
+        }
+        first
+    }
+
+    #[test]
+    fn chained_moves_are_order_independent() {
+        let state = state_with(&[
+            (c(0, 0), Space::PlayerEmpty { owner: 0, units: 5 }),
+            (c(1, 0), Space::PlayerEmpty { owner: 0, units: 3 }),
+            (c(2, 0), Space::PlayerEmpty { owner: 1, units: 2 }),
+            (c(3, 0), Space::PlayerEmpty { owner: 1, units: 4 }),
+        ]);
+        let result = resolve_in_every_order(
+            &state,
+            &[
+                mv(0, 5, c(0, 0), c(1, 0)),
+                mv(0, 3, c(1, 0), c(2, 0)),
+                mv(1, 4, c(3, 0), c(2, 0)),
+            ],
+        );
+
+        assert_eq!(
+            result.spaces[c(0, 0)],
+            Space::PlayerEmpty { owner: 0, units: 0 }
+        );
+        assert_eq!(
+            result.spaces[c(1, 0)],
+            Space::PlayerEmpty { owner: 0, units: 5 }
+        );
+        assert_eq!(
+            result.spaces[c(2, 0)],
+            Space::PlayerEmpty {
+                owner: 1,
+                units: 2 + 4 - 3
+            }
+        );
+    }
+
+    #[test]
+    fn attackers_wear_each_other_down_before_fighting_defender() {
+        let state = state_with(&[
+            (
+                c(1, 1),
+                Space::NeutralTown {
+                    units: 3,
+                    growth: 1,
+                    garrison: 3,
+                },
+            ),
+            (
+                c(0, 1),
+                Space::PlayerEmpty {
+                    owner: 0,
+                    units: 10,
+                },
+            ),
+            (c(2, 1), Space::PlayerEmpty { owner: 1, units: 4 }),
+            (c(1, 0), Space::PlayerEmpty { owner: 2, units: 6 }),
+        ]);
+        let result = resolve_in_every_order(
+            &state,
+            &[
+                mv(0, 10, c(0, 1), c(1, 1)),
+                mv(1, 4, c(2, 1), c(1, 1)),
+                mv(2, 6, c(1, 0), c(1, 1)),
+            ],
+        );
+
+        // 10, 4, 6 -> 6, 2 -> 4, which beats the 3 defenders
+        assert_eq!(
+            result.spaces[c(1, 1)],
+            Space::PlayerTown {
+                owner: 0,
+                units: 1,
+                growth: 1
+            }
+        );
+    }
+
+    #[test]
+    fn report_has_moves_and_battles() {
+        let mut state = state_with(&[
+            (
+                c(1, 1),
+                Space::NeutralTown {
+                    units: 3,
+                    growth: 1,
+                    garrison: 3,
+                },
+            ),
+            (c(0, 1), Space::PlayerEmpty { owner: 0, units: 2 }),
+            (c(2, 1), Space::PlayerEmpty { owner: 1, units: 4 }),
+            (c(5, 5), Space::PlayerEmpty { owner: 1, units: 1 }),
+            (c(5, 6), Space::PlayerEmpty { owner: 1, units: 1 }),
+        ]);
+        let report = state
+            .handle_moves(vec![
+                mv(1, 4, c(2, 1), c(1, 1)),
+                mv(0, 2, c(0, 1), c(1, 1)),
+                mv(1, 1, c(5, 5), c(5, 6)),
+            ])
+            .expect("Moves are valid");
+
+        assert_eq!(
+            report.moves,
+            vec![
+                mv(0, 2, c(0, 1), c(1, 1)),
+                mv(1, 4, c(2, 1), c(1, 1)),
+                mv(1, 1, c(5, 5), c(5, 6)),
+            ]
+        );
+        // Reinforcing your own space isn't a battle
+        assert_eq!(
+            report.battles,
+            vec![Battle {
+                at: c(1, 1),
+                attackers: vec![Army { owner: 0, units: 2 }, Army { owner: 1, units: 4 }],
+                defender: None,
+                defending_units: 3,
+                captured: false,
+            }]
+        );
+    }
+
+    #[test]
+    fn armies_meet_in_the_middle() {
+        let state = state_with(&[
+            (c(0, 0), Space::PlayerEmpty { owner: 0, units: 7 }),
+            (c(1, 0), Space::PlayerEmpty { owner: 1, units: 3 }),
+        ]);
+        let result = resolve_in_every_order(
+            &state,
+            &[mv(0, 7, c(0, 0), c(1, 0)), mv(1, 3, c(1, 0), c(0, 0))],
+        );
+
+        assert_eq!(
+            result.spaces[c(0, 0)],
+            Space::PlayerEmpty { owner: 0, units: 0 }
+        );
+        assert_eq!(
+            result.spaces[c(1, 0)],
+            Space::PlayerEmpty { owner: 0, units: 4 }
+        );
+    }
+
+    #[test]
+    fn duplicate_moves_cannot_create_units() {
+        let state = state_with(&[(c(0, 0), Space::PlayerEmpty { owner: 0, units: 4 })]);
+        let result = resolve_in_every_order(
+            &state,
+            &[mv(0, 4, c(0, 0), c(0, 1)), mv(0, 4, c(0, 0), c(0, 1))],
+        );
+
+        assert_eq!(
+            result.spaces[c(0, 1)],
+            Space::PlayerEmpty { owner: 0, units: 4 }
+        );
+    }
+
+    #[test]
+    fn units_passed_along_own_chain_are_conserved() {
+        let state = state_with(&[
+            (c(0, 0), Space::PlayerEmpty { owner: 0, units: 5 }),
+            (c(1, 0), Space::PlayerEmpty { owner: 0, units: 3 }),
+            (c(2, 0), Space::PlayerEmpty { owner: 0, units: 2 }),
+        ]);
+        let result = resolve_in_every_order(
+            &state,
+            &[
+                mv(0, 5, c(0, 0), c(1, 0)),
+                mv(0, 3, c(1, 0), c(2, 0)),
+                mv(0, 2, c(2, 0), c(3, 0)),
+            ],
+        );
+
+        assert_eq!(
+            [0, 1, 2, 3].map(|x| result.spaces[c(x, 0)]),
+            [
+                Space::PlayerEmpty { owner: 0, units: 0 },
+                Space::PlayerEmpty { owner: 0, units: 5 },
+                Space::PlayerEmpty { owner: 0, units: 3 },
+                Space::PlayerEmpty { owner: 0, units: 2 },
+            ]
+        );
+    }
+
+    #[test]
+    fn armies_of_the_same_owner_swap_unharmed() {
+        let state = state_with(&[
+            (c(0, 0), Space::PlayerCapital { owner: 0, units: 6 }),
+            (c(1, 0), Space::PlayerEmpty { owner: 0, units: 2 }),
+        ]);
+        let result = resolve_in_every_order(
+            &state,
+            &[mv(0, 6, c(0, 0), c(1, 0)), mv(0, 2, c(1, 0), c(0, 0))],
+        );
+
+        assert_eq!(
+            result.spaces[c(0, 0)],
+            Space::PlayerCapital { owner: 0, units: 2 }
+        );
+        assert_eq!(
+            result.spaces[c(1, 0)],
+            Space::PlayerEmpty { owner: 0, units: 6 }
+        );
+    }
+
+    #[test]
+    fn space_under_attack_is_not_vacated() {
+        let state = state_with(&[
+            (c(0, 0), Space::PlayerEmpty { owner: 1, units: 4 }),
+            (
+                c(1, 0),
+                Space::PlayerTown {
+                    owner: 0,
+                    units: 6,
+                    growth: 1,
+                },
+            ),
+            (c(2, 0), Space::PlayerEmpty { owner: 0, units: 1 }),
+        ]);
+        let result = resolve_in_every_order(
+            &state,
+            &[mv(1, 4, c(0, 0), c(1, 0)), mv(0, 6, c(1, 0), c(2, 0))],
+        );
+
+        assert_eq!(
+            result.spaces[c(1, 0)],
+            Space::PlayerTown {
+                owner: 0,
+                units: 2,
+                growth: 1
+            }
+        );
+        assert_eq!(
+            result.spaces[c(2, 0)],
+            Space::PlayerEmpty { owner: 0, units: 1 }
+        );
+    }
+
+    #[test]
+    fn held_army_no_longer_meets_in_the_middle() {
+        let state = state_with(&[
+            (c(0, 0), Space::PlayerEmpty { owner: 1, units: 5 }),
+            (c(1, 0), Space::PlayerEmpty { owner: 0, units: 8 }),
+            (c(1, 1), Space::PlayerEmpty { owner: 2, units: 2 }),
+        ]);
+        let result = resolve_in_every_order(
+            &state,
+            &[
+                mv(1, 5, c(0, 0), c(1, 0)),
+                mv(0, 8, c(1, 0), c(0, 0)),
+                mv(2, 2, c(1, 1), c(1, 0)),
+            ],
+        );
+
+        // Player 0 holds (1, 0) against both attackers, who first wear each other down
+        assert_eq!(
+            result.spaces[c(0, 0)],
+            Space::PlayerEmpty { owner: 1, units: 0 }
+        );
+        assert_eq!(
+            result.spaces[c(1, 0)],
+            Space::PlayerEmpty { owner: 0, units: 5 }
+        );
+    }
+
+    #[test]
+    fn invalid_moves_leave_state_untouched() {
+        let state = state_with(&[
+            (c(0, 0), Space::PlayerEmpty { owner: 0, units: 5 }),
+            (c(1, 0), Space::Mountain),
+            (c(0, 1), Space::PlayerEmpty { owner: 1, units: 5 }),
+        ]);
+
+        for (m, err) in [
+            (mv(0, 5, c(0, 0), c(1, 0)), MoveError::IntoMountain),
+            (mv(0, 5, c(0, 0), c(1, 1)), MoveError::NotAdjacent),
+            (mv(0, 5, c(0, 1), c(0, 2)), MoveError::NotOwned),
+            (mv(0, 5, c(0, 0), c(usize::MAX, 0)), MoveError::OutOfBounds),
+        ] {
+            let mut result = state.clone();
+            assert_eq!(
+                result.handle_moves(vec![mv(1, 5, c(0, 1), c(0, 2)), m]),
+                Err(err)
+            );
+            assert_eq!(result.spaces, state.spaces);
+        }
+    }
+
+    #[test]
+    fn validate_move_rejects_out_of_bounds_responses() {
+        let state = state_with(&[]);
+
+        assert_eq!(
+            state.validate_move(0, c(BOARD_SIZE, 0), c(BOARD_SIZE - 1, 0)),
+            Err(MoveError::OutOfBounds)
+        );
+    }
+
+    #[test]
+    fn generation_fails_without_room_for_every_player() {
+        assert!(GameState::new(BOARD_SIZE * BOARD_SIZE, &TownConfig::default()).is_ok());
+        assert_eq!(
+            GameState::new(BOARD_SIZE * BOARD_SIZE + 1, &TownConfig::default()).unwrap_err(),
+            GenerationError::NotEnoughSpace {
+                num_players: BOARD_SIZE * BOARD_SIZE + 1
+            }
+        );
+    }
+
+    #[test]
+    fn step_matches_a_server_turn() {
+        let state = state_with(&[
+            (c(0, 0), Space::PlayerCapital { owner: 0, units: 3 }),
+            (
+                c(5, 5),
+                Space::PlayerTown {
+                    owner: 1,
+                    units: 2,
+                    growth: 1,
+                },
+            ),
+        ]);
+
+        let mut stepped = state.clone();
+        stepped
+            .step(vec![mv(0, 3, c(0, 0), c(1, 0))])
+            .expect("Move is valid");
+
+        let mut manual = state.clone();
+        manual
+            .handle_moves(vec![mv(0, 3, c(0, 0), c(1, 0))])
+            .expect("Move is valid");
+        manual.populate_spaces();
+        manual.turn += 1;
+
+        assert_eq!(stepped.spaces, manual.spaces);
+        assert_eq!(stepped.turn, 2);
+        assert_eq!(stepped.winner(), None);
+    }
+
+    #[test]
+    fn swamps_drain_until_unowned() {
+        let mut state = state_with(&[
+            (c(0, 0), Space::PlayerCapital { owner: 0, units: 5 }),
+            (c(1, 0), Space::Swamp),
+        ]);
+
+        state
+            .step(vec![mv(0, 3, c(0, 0), c(1, 0))])
+            .expect("Move is valid");
+        assert_eq!(
+            state.spaces[c(1, 0)],
+            Space::PlayerSwamp { owner: 0, units: 2 }
+        );
+
+        state.end_turn();
+        state.end_turn();
+        assert_eq!(state.spaces[c(1, 0)], Space::Swamp);
+    }
+
+    #[test]
+    fn towns_grow_by_their_multiplier_and_neutral_towns_regenerate() {
+        let mut state = state_with(&[
+            (
+                c(0, 0),
+                Space::PlayerTown {
+                    owner: 0,
+                    units: 1,
+                    growth: 3,
+                },
+            ),
+            (
+                c(5, 5),
+                Space::NeutralTown {
+                    units: 18,
+                    growth: 2,
+                    garrison: 20,
+                },
+            ),
+        ]);
+        state.turn = 10;
+
+        state.end_turn();
+        assert_eq!(state.spaces[c(0, 0)].get_units(), 4);
+        assert_eq!(state.spaces[c(5, 5)].get_units(), 19);
+
+        state.end_turn();
+        assert_eq!(state.spaces[c(0, 0)].get_units(), 4);
+        assert_eq!(state.spaces[c(5, 5)].get_units(), 19);
+    }
+
+    #[test]
+    fn neutral_towns_regenerate_only_up_to_their_garrison() {
+        let mut state = state_with(&[
+            (
+                c(5, 5),
+                Space::NeutralTown {
+                    units: 18,
+                    growth: 1,
+                    garrison: 20,
+                },
+            ),
+            (c(0, 0), Space::PlayerCapital { owner: 0, units: 7 }),
+            (c(9, 9), Space::PlayerCapital { owner: 1, units: 4 }),
+        ]);
+        state.neutralize(0);
+
+        for _ in 0..10 * NEUTRAL_TOWN_REGENERATION_TURNS {
+            state.end_turn();
+        }
+        assert_eq!(state.spaces[c(5, 5)].get_units(), 20);
+        assert_eq!(state.spaces[c(0, 0)].get_units(), 7);
+    }
+
+    #[test]
+    fn neutralized_players_lose_everything() {
+        let mut state = state_with(&[
+            (c(0, 0), Space::PlayerCapital { owner: 0, units: 7 }),
+            (c(1, 0), Space::PlayerEmpty { owner: 0, units: 3 }),
+            (c(2, 0), Space::PlayerSwamp { owner: 0, units: 2 }),
+            (c(5, 5), Space::PlayerCapital { owner: 1, units: 4 }),
+        ]);
+
+        state.neutralize(0);
+
+        assert_eq!(
+            state.spaces[c(0, 0)],
+            Space::NeutralTown {
+                units: 7,
+                growth: 1,
+                garrison: 7
+            }
+        );
+        assert_eq!(state.spaces[c(1, 0)], Space::Empty);
+        assert_eq!(state.spaces[c(2, 0)], Space::Swamp);
+        assert_eq!(state.winner(), Some(1));
+    }
+
+    #[test]
+    fn last_player_with_land_wins() {
+        let mut state = state_with(&[
+            (c(0, 0), Space::PlayerEmpty { owner: 0, units: 5 }),
+            (c(1, 0), Space::PlayerCapital { owner: 1, units: 1 }),
+        ]);
+
+        state
+            .step(vec![mv(0, 5, c(0, 0), c(1, 0))])
+            .expect("Move is valid");
+
+        assert_eq!(state.alive_players(), BTreeSet::from([0]));
+        assert_eq!(state.winner(), Some(0));
+    }
+}
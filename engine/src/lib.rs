@@ -0,0 +1,7 @@
+//! The rules of the game, shared by the server and by bots that want to simulate future turns.
+
+mod game_state;
+mod towns;
+
+pub use game_state::{Army, Battle, GameState, GenerationError, Move, MoveError, TurnReport};
+pub use towns::TownConfig;
@@ -0,0 +1,93 @@
+use model::Space;
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::GenerationError;
+
+/// How neutral towns are set up when a board is generated
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TownConfig {
+    /// Each town starts with a garrison picked evenly between these, inclusive
+    pub min_garrison: usize,
+    pub max_garrison: usize,
+    /// Each town's growth multiplier is picked evenly from these, so repeating a value makes it
+    /// more likely
+    pub growth: Vec<usize>,
+}
+
+impl Default for TownConfig {
+    fn default() -> Self {
+        TownConfig {
+            min_garrison: 30,
+            max_garrison: 70,
+            growth: vec![1],
+        }
+    }
+}
+
+impl TownConfig {
+    /// Whether every town can be drawn from this config
+    pub fn check(&self) -> Result<(), GenerationError> {
+        if self.min_garrison > self.max_garrison {
+            Err(GenerationError::EmptyGarrisonRange {
+                min: self.min_garrison,
+                max: self.max_garrison,
+            })
+        } else if self.growth.is_empty() {
+            Err(GenerationError::NoTownGrowth)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// A neutral town drawn from the configured distributions. The config should already be checked.
+    pub fn random_town(&self, rng: &mut impl Rng) -> Space {
+        let garrison = rng.gen_range(self.min_garrison..=self.max_garrison);
+        Space::NeutralTown {
+            units: garrison,
+            growth: self.growth.choose(rng).copied().unwrap_or(1),
+            garrison,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::thread_rng;
+
+    use super::*;
+
+    #[test]
+    fn towns_come_from_the_configured_distributions() {
+        let config = TownConfig {
+            min_garrison: 10,
+            max_garrison: 12,
+            growth: vec![2, 3],
+        };
+        config.check().expect("Config is valid");
+        for _ in 0..100 {
+            let Space::NeutralTown {
+                units,
+                growth,
+                garrison,
+            } = config.random_town(&mut thread_rng())
+            else {
+                panic!("Towns start out neutral");
+            };
+            assert!((10..=12).contains(&units));
+            assert_eq!(garrison, units);
+            assert!(growth == 2 || growth == 3);
+        }
+
+        let backwards = TownConfig {
+            min_garrison: 5,
+            max_garrison: 4,
+            ..TownConfig::default()
+        };
+        assert_eq!(
+            backwards.check(),
+            Err(GenerationError::EmptyGarrisonRange { min: 5, max: 4 })
+        );
+    }
+}
@@ -1,48 +1,318 @@
-const socket = new WebSocket("/spectate");
+const params = new URLSearchParams(window.location.search);
+const gameId = params.get("game");
+// Whose perspective to watch from, if anyone's
+const perspectivePlayer = params.get("player");
+const socket =
+    gameId === null
+        ? null
+        : new WebSocket(`/spectate/${gameId}${perspectivePlayer === null ? "" : `?player=${perspectivePlayer}`}`);
 const contentDiv = document.getElementById("content");
+const gamesDiv = document.getElementById("games");
+const statusSpan = document.getElementById("status");
+const tickInput = document.getElementById("tick");
+const perspectiveSelect = document.getElementById("perspective");
 
-socket.addEventListener("open", (event) => {
-    console.log("Connected");
+function showControl(control) {
+    if (control.aborted) {
+        statusSpan.innerText = "Aborted";
+    } else if (control.paused) {
+        statusSpan.innerText = "Paused";
+    } else {
+        statusSpan.innerText = "Running";
+    }
+    if (document.activeElement !== tickInput) {
+        tickInput.value = control.tick_ms;
+    }
+}
+
+async function sendControl(path, body) {
+    if (gameId === null) {
+        return;
+    }
+    const response = await fetch(`/games/${gameId}/control/${path}`, {
+        method: "POST",
+        headers: { "Content-Type": "application/json" },
+        body: body === undefined ? undefined : JSON.stringify(body),
+    });
+    if (response.ok) {
+        showControl(await response.json());
+    }
+}
+
+for (const action of ["pause", "resume", "step"]) {
+    document.getElementById(action).addEventListener("click", () => sendControl(action));
+}
+document.getElementById("abort").addEventListener("click", () => {
+    if (confirm("Abort the game?")) {
+        sendControl("abort");
+    }
+});
+perspectiveSelect.addEventListener("change", () => {
+    params.delete("player");
+    if (perspectiveSelect.value !== "") {
+        params.set("player", perspectiveSelect.value);
+    }
+    window.location.search = `?${params}`;
 });
 
-// Listen for messages
-socket.addEventListener("message", (event) => {
-    const spaces = JSON.parse(event.data)["spaces"];
+function showPerspectiveOptions(players) {
+    if (perspectiveSelect.options.length > 1) {
+        return;
+    }
+    players.forEach((player, i) => {
+        const option = document.createElement("option");
+        option.value = i;
+        option.innerText = player.name;
+        perspectiveSelect.appendChild(option);
+    });
+    perspectiveSelect.value = perspectivePlayer ?? "";
+}
 
-    contentDiv.innerHTML = "";
+tickInput.addEventListener("change", () => {
+    const tick_ms = parseInt(tickInput.value);
+    if (tick_ms >= 0) {
+        sendControl("tick", { tick_ms });
+    }
+});
+
+async function showGames() {
+    const games = await (await fetch("/games")).json();
+    if (gameId === null) {
+        // Watch the newest game if none was picked
+        if (games.length > 0) {
+            window.location.search = `?game=${games[games.length - 1].id}`;
+        } else {
+            contentDiv.innerText = "No games yet. Start one with POST /games.";
+        }
+        return;
+    }
+
+    gamesDiv.replaceChildren(
+        ...games.map((game) => {
+            const link = document.createElement("a");
+            link.href = `?game=${game.id}`;
+            const names = game.players.map((player) => player.name).join(" vs ");
+            link.innerText = `${names} (${game.finished ? "finished" : `turn ${game.turn}`})`;
+            if (game.id === gameId) {
+                link.classList.add("currentGame");
+                if (!game.finished) {
+                    showControl(game.control);
+                }
+            }
+            return link;
+        })
+    );
+}
 
+showGames();
+setInterval(showGames, 5000);
+
+// The whole board, kept up to date from the snapshot and the diffs after it
+let spaces = [];
+// Every player's stats for every turn so far, indexed by turn then by player
+let history = [];
+
+const charts = [
+    { key: "land", title: "Land" },
+    { key: "units", title: "Units" },
+    { key: "towns", title: "Towns" },
+    { key: "rejected", title: "Rejected moves" },
+];
+
+// A line per player of one stat over every turn so far
+function drawChart(chart, players) {
+    const canvas = document.createElement("canvas");
+    canvas.width = 300;
+    canvas.height = 150;
+    canvas.classList.add("chart");
+    const ctx = canvas.getContext("2d");
+
+    const max = Math.max(1, ...history.flatMap((turn) => turn.map((stats) => stats[chart.key])));
+    const x = (turn) => (history.length > 1 ? (turn / (history.length - 1)) * canvas.width : 0);
+    const y = (value) => canvas.height - (value / max) * (canvas.height - 20);
+
+    ctx.font = "12px sans-serif";
+    ctx.fillText(`${chart.title} (max ${max})`, 4, 12);
+    players.forEach((player, i) => {
+        ctx.strokeStyle = player.color;
+        ctx.lineWidth = 2;
+        ctx.beginPath();
+        history.forEach((turn, t) => {
+            if (t === 0) {
+                ctx.moveTo(x(t), y(turn[i][chart.key]));
+            } else {
+                ctx.lineTo(x(t), y(turn[i][chart.key]));
+            }
+        });
+        ctx.stroke();
+    });
+    return canvas;
+}
+
+// How each player did over the whole game
+function showSummary(players) {
     const table = document.createElement("table");
+    table.classList.add("summary");
+    const header = document.createElement("tr");
+    for (const title of ["Player", "Land", "Units", "Towns", "Rejected moves", "Most land", "Most units"]) {
+        const th = document.createElement("th");
+        th.innerText = title;
+        header.appendChild(th);
+    }
+    table.appendChild(header);
+
+    const last = history[history.length - 1];
+    players.forEach((player, i) => {
+        const tr = document.createElement("tr");
+        tr.style.backgroundColor = player.color;
+        const stats = last[i];
+        const peak = (key) => Math.max(...history.map((turn) => turn[i][key]));
+        for (const value of [player.name, stats.land, stats.units, stats.towns, stats.rejected, peak("land"), peak("units")]) {
+            const td = document.createElement("td");
+            td.innerText = value;
+            tr.appendChild(td);
+        }
+        table.appendChild(tr);
+    });
+    return table;
+}
+
+socket?.addEventListener("open", (event) => {
+    console.log("Connected");
+});
+
+// The server closes the socket once the game is over
+socket?.addEventListener("close", (event) => {
+    console.log("Disconnected");
+});
+
+const SVG = "http://www.w3.org/2000/svg";
+
+// Arrows over the board for every army that moved last turn. Has to run once the board is on the
+// page, since it needs to know where each space was laid out.
+function drawMoves(board, cells, moves, players) {
+    const svg = document.createElementNS(SVG, "svg");
+    svg.classList.add("moves");
+    svg.setAttribute("width", board.offsetWidth);
+    svg.setAttribute("height", board.offsetHeight);
 
-    let playerStats = {};
+    const defs = document.createElementNS(SVG, "defs");
+    const marker = document.createElementNS(SVG, "marker");
+    marker.id = "arrowhead";
+    marker.setAttribute("viewBox", "0 0 10 10");
+    marker.setAttribute("refX", "5");
+    marker.setAttribute("refY", "5");
+    marker.setAttribute("markerWidth", "4");
+    marker.setAttribute("markerHeight", "4");
+    marker.setAttribute("orient", "auto-start-reverse");
+    const head = document.createElementNS(SVG, "path");
+    head.setAttribute("d", "M 0 0 L 10 5 L 0 10 z");
+    marker.appendChild(head);
+    defs.appendChild(marker);
+    svg.appendChild(defs);
+
+    const origin = board.getBoundingClientRect();
+    const center = (coordinate) => {
+        const rect = cells[coordinate.x][coordinate.y].getBoundingClientRect();
+        return {
+            x: rect.left - origin.left + rect.width / 2,
+            y: rect.top - origin.top + rect.height / 2,
+        };
+    };
+    for (const move of moves) {
+        const from = center(move.from);
+        const to = center(move.to);
+        // Stop short of the middle of the destination so the arrowhead doesn't hide its units
+        const end = { x: from.x + (to.x - from.x) * 0.7, y: from.y + (to.y - from.y) * 0.7 };
+        for (const [color, width, arrowhead] of [["black", 6, true], [players[move.owner].color, 3, false]]) {
+            const line = document.createElementNS(SVG, "line");
+            line.setAttribute("x1", from.x);
+            line.setAttribute("y1", from.y);
+            line.setAttribute("x2", end.x);
+            line.setAttribute("y2", end.y);
+            line.setAttribute("stroke", color);
+            line.setAttribute("stroke-width", width);
+            if (arrowhead) {
+                line.setAttribute("marker-end", "url(#arrowhead)");
+            }
+            svg.appendChild(line);
+        }
+    }
+    board.appendChild(svg);
+}
+
+// Outlines the spaces that were fought over last turn
+function showBattles(cells, battles, players) {
+    for (const battle of battles) {
+        const td = cells[battle.at.x][battle.at.y];
+        if (battle.captured) {
+            td.classList.add("captured");
+        }
+        // More than one attacker, or an attack on another player's space
+        if (battle.attackers.length > 1 || battle.defender !== null) {
+            td.classList.add("contested");
+        }
+        const attackers = battle.attackers
+            .map((army) => `${players[army.owner].name} (${army.units})`)
+            .join(", ");
+        const defender = battle.defender === null ? "neutral" : players[battle.defender].name;
+        td.title = `${attackers} attacked ${defender} (${battle.defending_units})${battle.captured ? ", captured" : ""}`;
+    }
+}
+
+// Marks towns that grow faster than usual, like `×2`
+function growthLabel(town) {
+    return town["growth"] > 1 ? `×${town["growth"]}` : "";
+}
+
+// A table of spaces, wrapped so that arrows can be drawn over it. `cells` is indexed by x then y,
+// like spaces.
+function renderBoard(spaces, players) {
+    const table = document.createElement("table");
+    const board = document.createElement("div");
+    board.classList.add("board");
+    board.appendChild(table);
+    const cells = [];
 
     for (const col of spaces) {
         const tr = document.createElement("tr");
+        const column = [];
+        cells.push(column);
         for (const cell of col) {
-            if (cell["owner"] !== undefined) {
-                if (playerStats[cell["owner"]] === undefined) {
-                    playerStats[cell["owner"]] = { land: 0, units: 0 };
-                }
-                playerStats[cell["owner"]].land++;
-                playerStats[cell["owner"]].units += cell["units"];
-            }
             const td = document.createElement("td");
+            column.push(td);
             if (cell["type"] == "PlayerCapital") {
                 td.innerHTML = `P<br />${cell["units"]}`;
-                td.classList.add(`player${cell["owner"]}`);
+                td.style.backgroundColor = players[cell["owner"]].color;
             } else if (cell["type"] == "PlayerTown") {
-                td.innerHTML = `p<br />${cell["units"]}`;
-                td.classList.add(`player${cell["owner"]}`);
+                td.innerHTML = `p${growthLabel(cell)}<br />${cell["units"]}`;
+                td.style.backgroundColor = players[cell["owner"]].color;
             } else if (cell["type"] == "NeutralTown") {
-                td.innerHTML = `t<br />${cell["units"]}`;
+                td.innerHTML = `t${growthLabel(cell)}<br />${cell["units"]}`;
                 td.classList.add(`neutralTown`);
             } else if (cell["type"] == "PlayerEmpty") {
                 td.innerHTML = `${cell["units"]}`;
-                td.classList.add(`player${cell["owner"]}`);
+                td.style.backgroundColor = players[cell["owner"]].color;
             } else if (cell["type"] == "Empty") {
                 td.innerHTML = "";
             } else if (cell["type"] == "Mountain") {
                 td.innerHTML = "M";
                 td.classList.add(`mountain`);
+            } else if (cell["type"] == "PlayerSwamp") {
+                td.innerHTML = `~<br />${cell["units"]}`;
+                td.style.backgroundColor = players[cell["owner"]].color;
+                td.title = "Swamp: loses a unit every turn";
+            } else if (cell["type"] == "Swamp") {
+                td.innerHTML = "~";
+                td.classList.add(`swamp`);
+                td.title = "Swamp: loses a unit every turn";
+            } else if (cell["type"] == "PlayerLookout") {
+                td.innerHTML = `L<br />${cell["units"]}`;
+                td.style.backgroundColor = players[cell["owner"]].color;
+                td.title = "Lookout: sees further through the fog";
+            } else if (cell["type"] == "Lookout") {
+                td.innerHTML = "L";
+                td.title = "Lookout: sees further through the fog";
             } else {
                 alert("Bad space type");
             }
@@ -51,14 +321,96 @@ socket.addEventListener("message", (event) => {
         }
         table.appendChild(tr);
     }
+    return { board, cells };
+}
+
+// Exactly what one player's bot was sent last turn, and what it answered. Returns the panel, and a
+// function to draw the bot's move once the panel is on the page.
+function showPerspective(perspective, players) {
+    const request = perspective.request;
+    const player = players[request.player];
+    const panel = document.createElement("div");
+    panel.classList.add("perspective");
+
+    const heading = document.createElement("h3");
+    heading.innerText = `Turn ${request.turn} as ${player.name}`;
+
+    const { board, cells } = renderBoard(request.spaces, players);
+    for (const hidden of perspective.hidden) {
+        cells[hidden.x][hidden.y].classList.add("fog");
+    }
+
+    const answer = document.createElement("p");
+    const response = perspective.response;
+    const move =
+        response === null
+            ? "No move"
+            : `(${response.from.x}, ${response.from.y}) to (${response.to.x}, ${response.to.y})`;
+    const outcome = perspective.outcome;
+    let status = outcome.status.replace("_", " ");
+    if (outcome.status === "rejected") {
+        status = `rejected: ${outcome.reason}`;
+    } else if (outcome.status === "failed") {
+        status = `failed: ${outcome.error}`;
+    }
+    answer.innerText = `${move} in ${perspective.elapsed_ms}ms, ${status}`;
+
+    const raw = document.createElement("details");
+    const summary = document.createElement("summary");
+    summary.innerText = "Turn request";
+    const json = document.createElement("pre");
+    json.innerText = JSON.stringify(request);
+    raw.replaceChildren(summary, json);
+
+    panel.replaceChildren(heading, board, answer, raw);
+    const drawResponse = () => {
+        if (response !== null) {
+            drawMoves(board, cells, [{ owner: request.player, ...response }], players);
+        }
+    };
+    return { panel, drawResponse };
+}
+
+// Listen for messages
+socket?.addEventListener("message", (event) => {
+    const data = JSON.parse(event.data);
+    if (data["type"] === "snapshot") {
+        spaces = data["spaces"];
+        history = data["history"];
+    } else {
+        for (const change of data["changes"]) {
+            spaces[change.x][change.y] = change.space;
+        }
+        history[data["turn"]] = data["stats"];
+    }
+    showControl(data["control"]);
+
+    contentDiv.innerHTML = "";
+
+    const players = data["players"];
+    showPerspectiveOptions(players);
+    const { board, cells } = renderBoard(spaces, players);
+    showBattles(cells, data["last_turn"].battles, players);
 
     const leaderboard = document.createElement("table");
-    for (const [key, value] of Object.entries(playerStats)) {
+    history[history.length - 1].forEach((value, key) => {
+        if (value.land === 0) {
+            // Out of the game
+            return;
+        }
         const tr = document.createElement("tr");
-        tr.classList.add(`player${key}`);
+        tr.style.backgroundColor = players[key].color;
 
         const td1 = document.createElement("td");
-        td1.innerText = `Player ${key}`;
+        td1.innerText = players[key].name;
+        const presence = data["presence"][key];
+        if (presence.status !== "connected") {
+            td1.innerText += ` (${presence.status} on turn ${presence.turn})`;
+            tr.classList.add("absent");
+        }
+        if (players[key].author) {
+            td1.title = `by ${players[key].author}`;
+        }
         tr.appendChild(td1);
 
         const td2 = document.createElement("td");
@@ -69,8 +421,56 @@ socket.addEventListener("message", (event) => {
         td3.innerText = `Units: ${value.units}`;
         tr.appendChild(td3);
 
+        const tdTowns = document.createElement("td");
+        tdTowns.innerText = `Towns: ${value.towns}`;
+        tr.appendChild(tdTowns);
+
+        const latency = data["latency"][key];
+        if (latency !== undefined) {
+            const td4 = document.createElement("td");
+            td4.innerText = `p50/p95/max: ${latency.p50_ms.toFixed(0)}/${latency.p95_ms.toFixed(0)}/${latency.max_ms.toFixed(0)}ms`;
+            tr.appendChild(td4);
+
+            const td5 = document.createElement("td");
+            td5.innerText = `Over budget: ${latency.over_budget}`;
+            if (latency.over_budget > 0) {
+                td5.classList.add("overBudget");
+            }
+            tr.appendChild(td5);
+        }
+
         leaderboard.appendChild(tr);
+    });
+
+    const chartsDiv = document.createElement("div");
+    chartsDiv.id = "charts";
+    chartsDiv.replaceChildren(...charts.map((chart) => drawChart(chart, players)));
+
+    // The board, next to what the chosen player saw of it
+    const boards = document.createElement("div");
+    boards.classList.add("boards");
+    boards.appendChild(board);
+    let drawResponse = () => {};
+    if (data["perspective"] !== undefined) {
+        const perspective = showPerspective(data["perspective"], players);
+        boards.appendChild(perspective.panel);
+        drawResponse = perspective.drawResponse;
     }
 
-    contentDiv.replaceChildren(table, leaderboard);
+    const summary = data["summary"];
+    if (summary) {
+        const gameOver = document.createElement("h2");
+        if (summary.aborted) {
+            gameOver.innerText = `Aborted after ${summary.turns} turns`;
+        } else if (summary.winner === null) {
+            gameOver.innerText = `No winner after ${summary.turns} turns`;
+        } else {
+            gameOver.innerText = `${players[summary.winner].name} won after ${summary.turns} turns`;
+        }
+        contentDiv.replaceChildren(gameOver, showSummary(players), boards, leaderboard, chartsDiv);
+    } else {
+        contentDiv.replaceChildren(boards, leaderboard, chartsDiv);
+    }
+    drawMoves(board, cells, data["last_turn"].moves, players);
+    drawResponse();
 });
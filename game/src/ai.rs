@@ -1,6 +1,6 @@
-use crate::game_state::Move;
+use std::{fmt::Display, time::Duration};
 
-use model::{Spaces, TurnRequest, TurnResponse};
+use model::{GameStart, TurnReply, TurnRequest};
 
 pub struct Ai {
     host: String,
@@ -38,27 +38,41 @@ impl Ai {
         Ok(Self { host, port })
     }
 
-    pub async fn make_move(&self, turn: usize, spaces: &Spaces, player: usize) -> Option<Move> {
-        let request_body = TurnRequest {
-            turn,
-            player,
-            spaces: *spaces,
-        };
-        let response: Option<TurnResponse> = reqwest::Client::new()
+    /// Tells the bot who it's playing against. Bots that don't care can just not handle `/start`.
+    pub async fn start_game(
+        &self,
+        start: &GameStart,
+        timeout: Duration,
+    ) -> Result<(), reqwest::Error> {
+        reqwest::Client::new()
+            .post(format!("http://{}:{}/start", self.host, self.port))
+            .timeout(timeout)
+            .json(start)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    /// Asks the bot for its move. A bot that takes longer than `timeout` counts as having failed.
+    pub async fn make_move(
+        &self,
+        request: &TurnRequest,
+        timeout: Duration,
+    ) -> Result<Option<TurnReply>, reqwest::Error> {
+        reqwest::Client::new()
             .post(format!("http://{}:{}", self.host, self.port))
-            .json(&request_body)
+            .timeout(timeout)
+            .json(request)
             .send()
-            .await
-            .unwrap()
+            .await?
             .json()
             .await
-            .unwrap();
+    }
+}
 
-        response.map(|r| Move {
-            owner: player,
-            units: spaces[r.from.x][r.from.y].get_units(),
-            from: r.from,
-            to: r.to,
-        })
+impl Display for Ai {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.host, self.port)
     }
 }
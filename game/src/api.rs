@@ -0,0 +1,278 @@
+use std::{sync::Arc, time::Duration};
+
+use axum::{
+    extract::{Path, Query, State, WebSocketUpgrade},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{delete, get, post},
+    Json, Router,
+};
+use model::BotRegistration;
+use serde::{Deserialize, Serialize};
+use tower_http::services::ServeDir;
+use tracing::{info, warn};
+
+use crate::{
+    control::ControlState,
+    lobby::{Lobby, LobbyError, RegisteredBot},
+    metrics::Metrics,
+    registry::{CreateGameError, Game, GameInfo, GameRegistry, PlayerSpec},
+    runner::GameConfig,
+    spectate::handle_socket,
+};
+
+#[derive(Clone)]
+pub struct AppState {
+    pub registry: Arc<GameRegistry>,
+    pub metrics: Arc<Metrics>,
+    pub lobby: Arc<Lobby>,
+    /// Used for games created without a config
+    pub defaults: GameConfig,
+}
+
+pub fn router(state: AppState) -> Router {
+    Router::new()
+        .route("/games", get(list_games_handler).post(create_game_handler))
+        .route("/games/:id", get(game_handler))
+        .route("/games/:id/control", get(control_handler))
+        .route("/games/:id/control/tick", post(tick_handler))
+        .route("/games/:id/control/:action", post(control_action_handler))
+        .route("/lobby/bots", get(list_bots_handler).post(register_handler))
+        .route("/lobby/bots/:id", delete(unregister_handler))
+        .route("/lobby/queue", get(queue_handler).post(enqueue_handler))
+        .route("/lobby/games", post(lobby_game_handler))
+        .route("/spectate/:id", get(ws_handler))
+        .route("/metrics", get(metrics_handler))
+        .fallback_service(ServeDir::new("game/data"))
+        .with_state(state)
+}
+
+fn find_game(state: &AppState, id: &str) -> Result<Arc<Game>, StatusCode> {
+    state.registry.get(id).ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn list_games_handler(State(state): State<AppState>) -> Json<Vec<GameInfo>> {
+    Json(state.registry.list())
+}
+
+#[derive(Deserialize)]
+struct CreateGameRequest {
+    /// Each `hostname:port` or just `port`, optionally prefixed with `name@`
+    players: Vec<String>,
+    config: Option<GameConfig>,
+}
+
+async fn create_game_handler(
+    State(state): State<AppState>,
+    Json(request): Json<CreateGameRequest>,
+) -> Result<(StatusCode, Json<GameInfo>), (StatusCode, String)> {
+    let players: Vec<PlayerSpec> = request
+        .players
+        .iter()
+        .map(|arg| PlayerSpec::from_arg(arg))
+        .collect();
+    create_game(&state, &players, request.config)
+}
+
+fn create_game(
+    state: &AppState,
+    players: &[PlayerSpec],
+    config: Option<GameConfig>,
+) -> Result<(StatusCode, Json<GameInfo>), (StatusCode, String)> {
+    let config = config.unwrap_or_else(|| state.defaults.clone());
+    match state.registry.create(players, config) {
+        Ok(game) => {
+            info!(id = game.id, endpoints = ?game.endpoints, "Game created");
+            Ok((StatusCode::CREATED, Json(game.info())))
+        }
+        Err(err) => {
+            warn!(?players, "Couldn't create game: {err}");
+            let status = match err {
+                CreateGameError::Logging(_) => StatusCode::INTERNAL_SERVER_ERROR,
+                _ => StatusCode::BAD_REQUEST,
+            };
+            Err((status, err.to_string()))
+        }
+    }
+}
+
+fn lobby_error(err: LobbyError) -> (StatusCode, String) {
+    let status = match err {
+        LobbyError::UnknownBot(_) => StatusCode::NOT_FOUND,
+        LobbyError::AlreadyQueued(_) => StatusCode::CONFLICT,
+        LobbyError::UnsupportedProtocol(_) | LobbyError::InvalidEndpoint(_) => {
+            StatusCode::BAD_REQUEST
+        }
+    };
+    (status, err.to_string())
+}
+
+fn player_specs(bots: &[RegisteredBot]) -> Vec<PlayerSpec> {
+    bots.iter()
+        .map(|bot| PlayerSpec {
+            endpoint: bot.registration.endpoint.clone(),
+            name: Some(bot.registration.name.clone()),
+            author: Some(bot.registration.author.clone()),
+            color: bot.registration.color.clone(),
+        })
+        .collect()
+}
+
+async fn list_bots_handler(State(state): State<AppState>) -> Json<Vec<RegisteredBot>> {
+    Json(state.lobby.bots())
+}
+
+async fn register_handler(
+    State(state): State<AppState>,
+    Json(registration): Json<BotRegistration>,
+) -> Result<(StatusCode, Json<RegisteredBot>), (StatusCode, String)> {
+    let bot = state.lobby.register(registration).map_err(lobby_error)?;
+    info!(
+        bot = bot.id,
+        name = bot.registration.name,
+        endpoint = bot.registration.endpoint,
+        "Bot registered"
+    );
+    Ok((StatusCode::CREATED, Json(bot)))
+}
+
+async fn unregister_handler(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<RegisteredBot>, StatusCode> {
+    let bot = state.lobby.unregister(&id).ok_or(StatusCode::NOT_FOUND)?;
+    info!(bot = bot.id, "Bot unregistered");
+    Ok(Json(bot))
+}
+
+async fn queue_handler(State(state): State<AppState>) -> Json<Vec<String>> {
+    Json(state.lobby.queue())
+}
+
+#[derive(Deserialize)]
+struct EnqueueRequest {
+    bot: String,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+enum EnqueueResponse {
+    /// Still waiting for enough bots to start a game
+    Queued {
+        queue: Vec<String>,
+    },
+    Matched {
+        game: GameInfo,
+    },
+}
+
+async fn enqueue_handler(
+    State(state): State<AppState>,
+    Json(request): Json<EnqueueRequest>,
+) -> Result<Json<EnqueueResponse>, (StatusCode, String)> {
+    let Some(matched) = state.lobby.enqueue(&request.bot).map_err(lobby_error)? else {
+        info!(bot = request.bot, "Bot queued");
+        return Ok(Json(EnqueueResponse::Queued {
+            queue: state.lobby.queue(),
+        }));
+    };
+    let (_, Json(game)) = create_game(&state, &player_specs(&matched), None)?;
+    Ok(Json(EnqueueResponse::Matched { game }))
+}
+
+#[derive(Deserialize)]
+struct LobbyGameRequest {
+    /// Ids of registered bots
+    bots: Vec<String>,
+    config: Option<GameConfig>,
+}
+
+async fn lobby_game_handler(
+    State(state): State<AppState>,
+    Json(request): Json<LobbyGameRequest>,
+) -> Result<(StatusCode, Json<GameInfo>), (StatusCode, String)> {
+    let bots = state.lobby.find(&request.bots).map_err(lobby_error)?;
+    create_game(&state, &player_specs(&bots), request.config)
+}
+
+async fn game_handler(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<GameInfo>, StatusCode> {
+    Ok(Json(find_game(&state, &id)?.info()))
+}
+
+async fn control_handler(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<ControlState>, StatusCode> {
+    Ok(Json(find_game(&state, &id)?.control.state()))
+}
+
+/// `POST /games/{id}/control/pause`, `.../resume`, `.../step` or `.../abort`
+async fn control_action_handler(
+    State(state): State<AppState>,
+    Path((id, action)): Path<(String, String)>,
+) -> Result<Json<ControlState>, StatusCode> {
+    let game = find_game(&state, &id)?;
+    let control = &game.control;
+    let new_state = match action.as_str() {
+        "pause" => control.pause(),
+        "resume" => control.resume(),
+        "step" => control.step(),
+        "abort" => control.abort(),
+        _ => return Err(StatusCode::NOT_FOUND),
+    };
+    info!(id, action, "Game control changed");
+    Ok(Json(new_state))
+}
+
+#[derive(Deserialize)]
+struct TickRequest {
+    tick_ms: u64,
+}
+
+async fn tick_handler(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(request): Json<TickRequest>,
+) -> Result<Json<ControlState>, StatusCode> {
+    let game = find_game(&state, &id)?;
+    info!(id, tick_ms = request.tick_ms, "Game tick rate changed");
+    Ok(Json(
+        game.control
+            .set_tick(Duration::from_millis(request.tick_ms)),
+    ))
+}
+
+async fn metrics_handler(State(state): State<AppState>) -> impl IntoResponse {
+    state.metrics.render()
+}
+
+#[derive(Deserialize)]
+struct SpectateQuery {
+    /// Whose perspective to watch from
+    player: Option<usize>,
+}
+
+/// `GET /spectate/{id}`, or `/spectate/{id}?player={seat}` to also see what that player's bot is
+/// sent and answers every turn
+async fn ws_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<SpectateQuery>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let game = find_game(&state, &id)?;
+    if query
+        .player
+        .is_some_and(|player| player >= game.players.len())
+    {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    info!(id, player = query.player, "New user connected.");
+
+    let guard = state.metrics.spectator_connected();
+
+    Ok(ws.on_upgrade(move |socket| handle_socket(socket, game, query.player, guard)))
+}
@@ -0,0 +1,166 @@
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::{sync::watch, time::sleep};
+
+/// How a game is currently being driven, as set through the control API
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ControlState {
+    pub paused: bool,
+    /// Turns to play before staying paused again
+    pub pending_steps: usize,
+    /// Time between turns
+    pub tick_ms: u64,
+    pub aborted: bool,
+}
+
+impl Default for ControlState {
+    fn default() -> Self {
+        ControlState {
+            paused: false,
+            pending_steps: 0,
+            tick_ms: 50,
+            aborted: false,
+        }
+    }
+}
+
+/// Lets the server pause, step, speed up or abort a running game
+#[derive(Debug)]
+pub struct GameControl {
+    state: watch::Sender<ControlState>,
+}
+
+impl Default for GameControl {
+    fn default() -> Self {
+        GameControl::new(ControlState::default())
+    }
+}
+
+impl GameControl {
+    pub fn new(state: ControlState) -> Self {
+        GameControl {
+            state: watch::Sender::new(state),
+        }
+    }
+
+    pub fn state(&self) -> ControlState {
+        *self.state.borrow()
+    }
+
+    pub fn pause(&self) -> ControlState {
+        self.update(|s| s.paused = true)
+    }
+
+    pub fn resume(&self) -> ControlState {
+        self.update(|s| {
+            s.paused = false;
+            s.pending_steps = 0;
+        })
+    }
+
+    /// Pauses the game if it isn't already, and plays one more turn
+    pub fn step(&self) -> ControlState {
+        self.update(|s| {
+            s.paused = true;
+            s.pending_steps += 1;
+        })
+    }
+
+    pub fn set_tick(&self, tick: Duration) -> ControlState {
+        self.update(|s| s.tick_ms = tick.as_millis() as u64)
+    }
+
+    pub fn abort(&self) -> ControlState {
+        self.update(|s| s.aborted = true)
+    }
+
+    fn update(&self, change: impl FnOnce(&mut ControlState)) -> ControlState {
+        self.state.send_modify(change);
+        self.state()
+    }
+
+    /// Waits until the next turn may be played. Returns `false` if the game was aborted instead.
+    pub async fn next_turn(&self) -> bool {
+        let mut changes = self.state.subscribe();
+        loop {
+            let mut go = None;
+            self.state.send_if_modified(|s| {
+                if s.aborted {
+                    go = Some(false);
+                    false
+                } else if !s.paused {
+                    go = Some(true);
+                    false
+                } else if s.pending_steps > 0 {
+                    s.pending_steps -= 1;
+                    go = Some(true);
+                    true
+                } else {
+                    false
+                }
+            });
+            if let Some(go) = go {
+                return go;
+            }
+            // We hold the sender, so this can't fail
+            let _ = changes.changed().await;
+        }
+    }
+
+    /// Waits for one tick, or less if the controls change in the meantime so that pausing and
+    /// aborting take effect straight away
+    pub async fn tick(&self) {
+        let mut changes = self.state.subscribe();
+        let tick = Duration::from_millis(self.state().tick_ms);
+        tokio::select! {
+            _ = sleep(tick) => {}
+            _ = changes.changed() => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use tokio::time::timeout;
+
+    async fn can_play(control: &GameControl) -> Option<bool> {
+        timeout(Duration::from_millis(20), control.next_turn())
+            .await
+            .ok()
+    }
+
+    #[tokio::test]
+    async fn paused_games_only_play_stepped_turns() {
+        let control = GameControl::default();
+        assert_eq!(can_play(&control).await, Some(true));
+
+        control.pause();
+        assert_eq!(can_play(&control).await, None);
+
+        control.step();
+        control.step();
+        assert_eq!(can_play(&control).await, Some(true));
+        assert_eq!(can_play(&control).await, Some(true));
+        assert_eq!(can_play(&control).await, None);
+
+        control.resume();
+        assert_eq!(can_play(&control).await, Some(true));
+    }
+
+    #[tokio::test]
+    async fn abort_wakes_a_paused_game() {
+        let control = Arc::new(GameControl::default());
+        control.pause();
+        let waiting = tokio::spawn({
+            let control = control.clone();
+            async move { control.next_turn().await }
+        });
+        sleep(Duration::from_millis(10)).await;
+        control.abort();
+
+        assert!(!waiting.await.unwrap());
+    }
+}
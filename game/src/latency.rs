@@ -0,0 +1,149 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// What happens to a move that arrives after the latency budget
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Penalty {
+    /// Count it and keep the move
+    #[default]
+    Flag,
+    /// Count it and throw the move away
+    Skip,
+}
+
+impl Penalty {
+    pub fn from_arg(arg: &str) -> Result<Self, String> {
+        match arg {
+            "flag" => Ok(Penalty::Flag),
+            "skip" => Ok(Penalty::Skip),
+            _ => Err(format!(
+                "Unknown latency penalty '{arg}'. Expected 'flag' or 'skip'."
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Budget {
+    pub limit: Duration,
+    pub penalty: Penalty,
+}
+
+/// Response times of every player over one game
+#[derive(Debug, Clone)]
+pub struct LatencyTracker {
+    samples: Vec<Vec<Duration>>,
+    over_budget: Vec<usize>,
+    budget: Option<Budget>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct LatencyStats {
+    pub requests: usize,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub max_ms: f64,
+    /// How many responses took longer than the budget
+    pub over_budget: usize,
+}
+
+impl LatencyTracker {
+    pub fn new(num_players: usize, budget: Option<Budget>) -> Self {
+        LatencyTracker {
+            samples: vec![vec![]; num_players],
+            over_budget: vec![0; num_players],
+            budget,
+        }
+    }
+
+    /// Records how long `player` took to answer, and returns whether the answer should be thrown
+    /// away for being late
+    pub fn record(&mut self, player: usize, elapsed: Duration) -> bool {
+        self.samples[player].push(elapsed);
+        match self.budget {
+            Some(budget) if elapsed > budget.limit => {
+                self.over_budget[player] += 1;
+                budget.penalty == Penalty::Skip
+            }
+            _ => false,
+        }
+    }
+
+    pub fn budget(&self) -> Option<Budget> {
+        self.budget
+    }
+
+    pub fn stats(&self) -> Vec<LatencyStats> {
+        self.samples
+            .iter()
+            .zip(&self.over_budget)
+            .map(|(samples, over_budget)| {
+                let mut sorted = samples.clone();
+                sorted.sort();
+                LatencyStats {
+                    requests: sorted.len(),
+                    p50_ms: millis(percentile(&sorted, 50)),
+                    p95_ms: millis(percentile(&sorted, 95)),
+                    max_ms: millis(sorted.last().copied().unwrap_or_default()),
+                    over_budget: *over_budget,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Nearest-rank percentile of already sorted samples
+fn percentile(sorted: &[Duration], p: usize) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = (p * sorted.len()).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentiles_use_nearest_rank() {
+        let mut tracker = LatencyTracker::new(2, None);
+        for ms in 1..=100 {
+            tracker.record(0, Duration::from_millis(ms));
+        }
+
+        let stats = tracker.stats();
+        assert_eq!(stats[0].requests, 100);
+        assert_eq!(stats[0].p50_ms, 50.0);
+        assert_eq!(stats[0].p95_ms, 95.0);
+        assert_eq!(stats[0].max_ms, 100.0);
+        assert_eq!(stats[1].requests, 0);
+        assert_eq!(stats[1].max_ms, 0.0);
+    }
+
+    #[test]
+    fn late_responses_are_counted_and_optionally_skipped() {
+        let budget = |penalty| {
+            Some(Budget {
+                limit: Duration::from_millis(10),
+                penalty,
+            })
+        };
+        let mut flag = LatencyTracker::new(1, budget(Penalty::Flag));
+        let mut skip = LatencyTracker::new(1, budget(Penalty::Skip));
+
+        assert!(!flag.record(0, Duration::from_millis(20)));
+        assert!(!flag.record(0, Duration::from_millis(10)));
+        assert!(skip.record(0, Duration::from_millis(20)));
+        assert!(!skip.record(0, Duration::from_millis(5)));
+
+        assert_eq!(flag.stats()[0].over_budget, 1);
+        assert_eq!(skip.stats()[0].over_budget, 1);
+    }
+}
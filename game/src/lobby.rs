@@ -0,0 +1,203 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    error::Error,
+    fmt::Display,
+    sync::Mutex,
+};
+
+use model::{BotRegistration, PROTOCOL_VERSION};
+use serde::Serialize;
+
+use crate::{
+    ai::Ai,
+    registry::{new_id, MIN_PLAYERS},
+};
+
+/// A bot that has registered itself with the lobby
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RegisteredBot {
+    pub id: String,
+    #[serde(flatten)]
+    pub registration: BotRegistration,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum LobbyError {
+    UnsupportedProtocol(u32),
+    InvalidEndpoint(String),
+    UnknownBot(String),
+    AlreadyQueued(String),
+}
+impl Display for LobbyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LobbyError::UnsupportedProtocol(version) => write!(
+                f,
+                "protocol version {version} isn't supported, this server speaks version {PROTOCOL_VERSION}"
+            ),
+            LobbyError::InvalidEndpoint(err) => f.write_str(err),
+            LobbyError::UnknownBot(id) => write!(f, "no bot is registered with id {id}"),
+            LobbyError::AlreadyQueued(id) => write!(f, "bot {id} is already queued"),
+        }
+    }
+}
+impl Error for LobbyError {}
+
+/// Bots that registered themselves, and the queue of bots waiting to be matched into a game
+#[derive(Debug)]
+pub struct Lobby {
+    bots: Mutex<BTreeMap<String, RegisteredBot>>,
+    queue: Mutex<VecDeque<String>>,
+    /// How many queued bots make a game, at least [`MIN_PLAYERS`]
+    match_size: usize,
+}
+
+impl Lobby {
+    pub fn new(match_size: usize) -> Self {
+        Lobby {
+            bots: Mutex::new(BTreeMap::new()),
+            queue: Mutex::new(VecDeque::new()),
+            match_size: match_size.max(MIN_PLAYERS),
+        }
+    }
+
+    /// Adds a bot to the lobby. A bot registering again from the same endpoint, say after a
+    /// restart, keeps its id.
+    pub fn register(&self, registration: BotRegistration) -> Result<RegisteredBot, LobbyError> {
+        if registration.protocol_version != PROTOCOL_VERSION {
+            return Err(LobbyError::UnsupportedProtocol(
+                registration.protocol_version,
+            ));
+        }
+        Ai::from_arg(&registration.endpoint).map_err(LobbyError::InvalidEndpoint)?;
+
+        let mut bots = self.bots.lock().unwrap();
+        let id = bots
+            .values()
+            .find(|bot| bot.registration.endpoint == registration.endpoint)
+            .map_or_else(new_id, |bot| bot.id.clone());
+        let bot = RegisteredBot {
+            id: id.clone(),
+            registration,
+        };
+        bots.insert(id, bot.clone());
+        Ok(bot)
+    }
+
+    pub fn unregister(&self, id: &str) -> Option<RegisteredBot> {
+        self.queue.lock().unwrap().retain(|queued| queued != id);
+        self.bots.lock().unwrap().remove(id)
+    }
+
+    pub fn bots(&self) -> Vec<RegisteredBot> {
+        self.bots.lock().unwrap().values().cloned().collect()
+    }
+
+    /// Looks up the bots with the given ids, in order
+    pub fn find(&self, ids: &[String]) -> Result<Vec<RegisteredBot>, LobbyError> {
+        let bots = self.bots.lock().unwrap();
+        ids.iter()
+            .map(|id| {
+                bots.get(id)
+                    .cloned()
+                    .ok_or_else(|| LobbyError::UnknownBot(id.clone()))
+            })
+            .collect()
+    }
+
+    pub fn queue(&self) -> Vec<String> {
+        self.queue.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Puts a bot in the matchmaking queue. Once enough bots are waiting, takes the ones that have
+    /// waited longest out of the queue and returns them, to be put in a game together.
+    pub fn enqueue(&self, id: &str) -> Result<Option<Vec<RegisteredBot>>, LobbyError> {
+        let bot = self.find(&[id.to_string()])?.remove(0);
+        let mut queue = self.queue.lock().unwrap();
+        if queue.iter().any(|queued| queued == id) {
+            return Err(LobbyError::AlreadyQueued(id.to_string()));
+        }
+        queue.push_back(bot.id);
+
+        if queue.len() < self.match_size {
+            return Ok(None);
+        }
+        let matched: Vec<String> = queue.drain(..self.match_size).collect();
+        drop(queue);
+        self.find(&matched).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registration(name: &str, port: u16) -> BotRegistration {
+        BotRegistration {
+            name: name.to_string(),
+            author: "someone".to_string(),
+            endpoint: format!("localhost:{port}"),
+            protocol_version: PROTOCOL_VERSION,
+            color: None,
+        }
+    }
+
+    #[test]
+    fn re_registering_an_endpoint_keeps_its_id() {
+        let lobby = Lobby::new(2);
+        let first = lobby.register(registration("first", 8081)).unwrap();
+        let again = lobby.register(registration("renamed", 8081)).unwrap();
+        let other = lobby.register(registration("other", 8082)).unwrap();
+
+        assert_eq!(first.id, again.id);
+        assert_ne!(first.id, other.id);
+        assert_eq!(lobby.bots().len(), 2);
+        assert_eq!(
+            lobby.find(&[first.id]).unwrap()[0].registration.name,
+            "renamed"
+        );
+    }
+
+    #[test]
+    fn rejects_bad_registrations() {
+        let lobby = Lobby::new(2);
+        let mut old = registration("old", 8081);
+        old.protocol_version = PROTOCOL_VERSION + 1;
+        let mut nowhere = registration("nowhere", 8081);
+        nowhere.endpoint = "nowhere".to_string();
+
+        assert_eq!(
+            lobby.register(old),
+            Err(LobbyError::UnsupportedProtocol(PROTOCOL_VERSION + 1))
+        );
+        assert!(matches!(
+            lobby.register(nowhere),
+            Err(LobbyError::InvalidEndpoint(_))
+        ));
+        assert!(lobby.bots().is_empty());
+    }
+
+    #[test]
+    fn matches_queued_bots_in_order() {
+        let lobby = Lobby::new(2);
+        let a = lobby.register(registration("a", 8081)).unwrap();
+        let b = lobby.register(registration("b", 8082)).unwrap();
+        let c = lobby.register(registration("c", 8083)).unwrap();
+
+        assert_eq!(lobby.enqueue(&a.id), Ok(None));
+        assert_eq!(
+            lobby.enqueue(&a.id),
+            Err(LobbyError::AlreadyQueued(a.id.clone()))
+        );
+        assert_eq!(lobby.enqueue(&b.id), Ok(Some(vec![a, b])));
+        assert_eq!(lobby.enqueue(&c.id), Ok(None));
+        assert_eq!(lobby.queue(), vec![c.id.clone()]);
+        assert_eq!(
+            lobby.enqueue("missing"),
+            Err(LobbyError::UnknownBot("missing".to_string()))
+        );
+
+        lobby.unregister(&c.id);
+        assert!(lobby.queue().is_empty());
+    }
+}
@@ -0,0 +1,91 @@
+use std::{
+    fs::{self, File},
+    io,
+    path::PathBuf,
+    sync::Arc,
+};
+
+use tracing::Dispatch;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+impl LogFormat {
+    pub fn from_arg(arg: &str) -> Result<Self, String> {
+        match arg {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(format!(
+                "Unknown log format '{arg}'. Expected 'text' or 'json'."
+            )),
+        }
+    }
+}
+
+/// Where logs go, read from `LOG_FORMAT` (`text` or `json`), `LOG_DIR` and `RUST_LOG`
+#[derive(Debug, Clone)]
+pub struct LogConfig {
+    pub format: LogFormat,
+    /// Every game also logs to `<dir>/<game id>.log`, as JSON
+    pub dir: PathBuf,
+}
+
+impl LogConfig {
+    pub fn from_env() -> Result<Self, String> {
+        let format = match std::env::var("LOG_FORMAT") {
+            Ok(format) => LogFormat::from_arg(&format)?,
+            Err(_) => LogFormat::Text,
+        };
+        let dir = std::env::var("LOG_DIR").unwrap_or_else(|_| "logs".to_string());
+        Ok(LogConfig {
+            format,
+            dir: dir.into(),
+        })
+    }
+
+    /// Logs everything outside of a game to stdout
+    pub fn init(&self) {
+        tracing_subscriber::registry()
+            .with(self.stdout_layer())
+            .init();
+    }
+
+    /// A subscriber for everything that happens in one game, which logs to stdout and the game's
+    /// own file. Run the game with it using `WithSubscriber::with_subscriber`.
+    pub fn game_dispatch(&self, game_id: &str) -> io::Result<Dispatch> {
+        fs::create_dir_all(&self.dir)?;
+        let file = File::create(self.dir.join(format!("{game_id}.log")))?;
+        let file_layer = tracing_subscriber::fmt::layer()
+            .json()
+            .with_writer(Arc::new(file))
+            .with_filter(filter());
+        Ok(tracing_subscriber::registry()
+            .with(self.stdout_layer())
+            .with(file_layer)
+            .into())
+    }
+
+    fn stdout_layer<S>(&self) -> Box<dyn Layer<S> + Send + Sync>
+    where
+        S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+    {
+        match self.format {
+            LogFormat::Text => tracing_subscriber::fmt::layer()
+                .with_filter(filter())
+                .boxed(),
+            LogFormat::Json => tracing_subscriber::fmt::layer()
+                .json()
+                .with_filter(filter())
+                .boxed(),
+        }
+    }
+}
+
+/// `RUST_LOG`, or info and up if that isn't set
+fn filter() -> EnvFilter {
+    EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"))
+}
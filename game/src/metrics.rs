@@ -0,0 +1,268 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{
+        atomic::{AtomicI64, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use engine::MoveError;
+
+/// Upper bounds of the histogram buckets, in seconds
+const BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+#[derive(Debug, Default, Clone)]
+struct Histogram {
+    /// Not cumulative; `render` adds them up
+    counts: [u64; BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        if let Some(bucket) = BUCKETS.iter().position(|bound| seconds <= *bound) {
+            self.counts[bucket] += 1;
+        }
+        self.count += 1;
+        self.sum += seconds;
+    }
+
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        let mut cumulative = 0;
+        for (bound, count) in BUCKETS.iter().zip(self.counts) {
+            cumulative += count;
+            let _ = writeln!(out, "{name}_bucket{{{labels}le=\"{bound}\"}} {cumulative}");
+        }
+        let _ = writeln!(out, "{name}_bucket{{{labels}le=\"+Inf\"}} {}", self.count);
+        let labels = match labels.trim_end_matches(',') {
+            "" => String::new(),
+            labels => format!("{{{labels}}}"),
+        };
+        let _ = writeln!(out, "{name}_sum{labels} {}", self.sum);
+        let _ = writeln!(out, "{name}_count{labels} {}", self.count);
+    }
+}
+
+/// Server-wide counters, exposed at `/metrics` in the Prometheus text format
+#[derive(Debug, Default)]
+pub struct Metrics {
+    turns_processed: AtomicU64,
+    turn_duration: Mutex<Histogram>,
+    /// By game and seat, since seats are only unique within a game
+    bot_latency: Mutex<BTreeMap<(String, usize), Histogram>>,
+    bot_errors: Mutex<BTreeMap<(String, usize), u64>>,
+    rejected_moves: Mutex<BTreeMap<&'static str, u64>>,
+    spectators: AtomicI64,
+    games_in_progress: AtomicI64,
+}
+
+impl Metrics {
+    pub fn turn_processed(&self, duration: Duration) {
+        self.turns_processed.fetch_add(1, Ordering::Relaxed);
+        self.turn_duration.lock().unwrap().observe(duration);
+    }
+
+    pub fn bot_responded(&self, game: &str, player: usize, duration: Duration) {
+        self.bot_latency
+            .lock()
+            .unwrap()
+            .entry((game.to_string(), player))
+            .or_default()
+            .observe(duration);
+    }
+
+    pub fn bot_failed(&self, game: &str, player: usize) {
+        *self
+            .bot_errors
+            .lock()
+            .unwrap()
+            .entry((game.to_string(), player))
+            .or_default() += 1;
+    }
+
+    pub fn move_rejected(&self, reason: &'static str) {
+        *self
+            .rejected_moves
+            .lock()
+            .unwrap()
+            .entry(reason)
+            .or_default() += 1;
+    }
+
+    pub fn game_started(&self) {
+        self.games_in_progress.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn game_ended(&self) {
+        self.games_in_progress.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// Counts a spectator as connected until the returned guard is dropped
+    pub fn spectator_connected(self: &Arc<Self>) -> SpectatorGuard {
+        self.spectators.fetch_add(1, Ordering::Relaxed);
+        SpectatorGuard(self.clone())
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+
+        header(
+            &mut out,
+            "generals_turns_processed_total",
+            "counter",
+            "Turns played",
+        );
+        let _ = writeln!(
+            out,
+            "generals_turns_processed_total {}",
+            self.turns_processed.load(Ordering::Relaxed)
+        );
+
+        header(
+            &mut out,
+            "generals_turn_duration_seconds",
+            "histogram",
+            "Time taken by each turn of the game loop, including bot requests",
+        );
+        self.turn_duration
+            .lock()
+            .unwrap()
+            .render(&mut out, "generals_turn_duration_seconds", "");
+
+        header(
+            &mut out,
+            "generals_bot_request_duration_seconds",
+            "histogram",
+            "Time taken by bots to respond",
+        );
+        for ((game, player), histogram) in self.bot_latency.lock().unwrap().iter() {
+            histogram.render(
+                &mut out,
+                "generals_bot_request_duration_seconds",
+                &format!("game=\"{game}\",player=\"{player}\","),
+            );
+        }
+
+        header(
+            &mut out,
+            "generals_bot_errors_total",
+            "counter",
+            "Requests to bots that failed",
+        );
+        for ((game, player), count) in self.bot_errors.lock().unwrap().iter() {
+            let _ = writeln!(
+                out,
+                "generals_bot_errors_total{{game=\"{game}\",player=\"{player}\"}} {count}"
+            );
+        }
+
+        header(
+            &mut out,
+            "generals_rejected_moves_total",
+            "counter",
+            "Moves from bots that weren't played",
+        );
+        for (reason, count) in self.rejected_moves.lock().unwrap().iter() {
+            let _ = writeln!(
+                out,
+                "generals_rejected_moves_total{{reason=\"{reason}\"}} {count}"
+            );
+        }
+
+        header(
+            &mut out,
+            "generals_spectators",
+            "gauge",
+            "Connected spectators",
+        );
+        let _ = writeln!(
+            out,
+            "generals_spectators {}",
+            self.spectators.load(Ordering::Relaxed)
+        );
+
+        header(
+            &mut out,
+            "generals_games_in_progress",
+            "gauge",
+            "Games being played",
+        );
+        let _ = writeln!(
+            out,
+            "generals_games_in_progress {}",
+            self.games_in_progress.load(Ordering::Relaxed)
+        );
+
+        out
+    }
+}
+
+pub struct SpectatorGuard(Arc<Metrics>);
+
+impl Drop for SpectatorGuard {
+    fn drop(&mut self) {
+        self.0.spectators.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// The `reason` label for a move that failed validation
+pub fn rejection_reason(err: &MoveError) -> &'static str {
+    match err {
+        MoveError::OutOfBounds => "out_of_bounds",
+        MoveError::NotAdjacent => "not_adjacent",
+        MoveError::NotOwned => "not_owned",
+        MoveError::IntoMountain => "into_mountain",
+        MoveError::Space(_) => "space",
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_prometheus_text() {
+        let metrics = Arc::new(Metrics::default());
+        metrics.game_started();
+        metrics.turn_processed(Duration::from_millis(30));
+        metrics.bot_responded("abc", 1, Duration::from_millis(7));
+        metrics.bot_responded("def", 1, Duration::from_millis(70));
+        metrics.bot_failed("abc", 1);
+        metrics.move_rejected(rejection_reason(&MoveError::NotOwned));
+        let guard = metrics.spectator_connected();
+
+        let text = metrics.render();
+        assert!(text.contains("generals_turns_processed_total 1\n"));
+        assert!(text.contains("generals_turn_duration_seconds_bucket{le=\"0.025\"} 0\n"));
+        assert!(text.contains("generals_turn_duration_seconds_bucket{le=\"0.05\"} 1\n"));
+        assert!(text.contains("generals_turn_duration_seconds_count 1\n"));
+        assert!(text.contains(
+            "generals_bot_request_duration_seconds_bucket{game=\"abc\",player=\"1\",le=\"0.01\"} 1\n"
+        ));
+        assert!(text.contains(
+            "generals_bot_request_duration_seconds_bucket{game=\"def\",player=\"1\",le=\"0.01\"} 0\n"
+        ));
+        assert!(text.contains(
+            "generals_bot_request_duration_seconds_count{game=\"abc\",player=\"1\"} 1\n"
+        ));
+        assert!(text.contains("generals_bot_errors_total{game=\"abc\",player=\"1\"} 1\n"));
+        assert!(!text.contains("generals_bot_errors_total{game=\"def\""));
+        assert!(text.contains("generals_rejected_moves_total{reason=\"not_owned\"} 1\n"));
+        assert!(text.contains("generals_spectators 1\n"));
+        assert!(text.contains("generals_games_in_progress 1\n"));
+
+        drop(guard);
+        assert!(metrics.render().contains("generals_spectators 0\n"));
+    }
+}
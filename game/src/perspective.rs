@@ -0,0 +1,85 @@
+use model::{Board, Coordinate, Space, TurnRequest, TurnResponse};
+use serde::Serialize;
+
+/// What happened to a bot's response
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Outcome {
+    Accepted,
+    Passed,
+    Rejected {
+        reason: String,
+    },
+    /// The response came back after the latency budget, and the penalty is to skip it
+    OverBudget,
+    Failed {
+        error: String,
+    },
+    Surrendered,
+    /// The player had already left the game, so their bot wasn't asked
+    Absent,
+}
+
+impl Outcome {
+    /// Whether this counts as a rejected move in the game's stats
+    pub fn is_rejected(&self) -> bool {
+        matches!(self, Outcome::Rejected { .. } | Outcome::OverBudget)
+    }
+}
+
+/// One player's side of a turn: exactly what their bot was sent, and what it sent back
+#[derive(Debug, Clone, Serialize)]
+pub struct PlayerTurn {
+    pub request: TurnRequest,
+    /// Spaces the fog of war hid from the request
+    pub hidden: Vec<Coordinate>,
+    pub response: Option<TurnResponse>,
+    pub elapsed_ms: u64,
+    pub outcome: Outcome,
+}
+
+impl PlayerTurn {
+    /// The outcome starts out as [`Outcome::Passed`], to be filled in once the bot answers
+    pub fn new(turn: usize, player: usize, board: &Board, fog_of_war: bool) -> Self {
+        let (spaces, hidden) = if fog_of_war {
+            let hidden = Board::coordinates()
+                .filter(|c| board[*c] != Space::Mountain && !board.is_visible_to(player, *c))
+                .collect();
+            (board.fogged(player), hidden)
+        } else {
+            (*board, vec![])
+        };
+        PlayerTurn {
+            request: TurnRequest {
+                turn,
+                player,
+                spaces,
+            },
+            hidden,
+            response: None,
+            elapsed_ms: 0,
+            outcome: Outcome::Passed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fog_of_war_only_changes_the_request_when_enabled() {
+        let mut board = Board::default();
+        board[Coordinate { x: 0, y: 0 }] = Space::PlayerCapital { owner: 0, units: 5 };
+        board[Coordinate { x: 9, y: 9 }] = Space::PlayerCapital { owner: 1, units: 5 };
+
+        let clear = PlayerTurn::new(3, 0, &board, false);
+        assert_eq!(clear.request.spaces, board);
+        assert!(clear.hidden.is_empty());
+
+        let fogged = PlayerTurn::new(3, 0, &board, true);
+        assert_eq!(fogged.request.spaces, board.fogged(0));
+        assert!(fogged.hidden.contains(&Coordinate { x: 9, y: 9 }));
+        assert!(!fogged.hidden.contains(&Coordinate { x: 1, y: 1 }));
+    }
+}
@@ -0,0 +1,128 @@
+use serde::Serialize;
+
+use crate::perspective::Outcome;
+
+/// Whether a player's bot is still taking part in the game
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Presence {
+    Connected,
+    /// Failed too many turns in a row, so it isn't asked for moves anymore. Its spaces stay where
+    /// they are until the grace period runs out.
+    Disconnected {
+        turn: usize,
+    },
+    Surrendered {
+        turn: usize,
+    },
+}
+
+/// Which players are still playing over one game
+#[derive(Debug, Clone)]
+pub struct PresenceTracker {
+    presence: Vec<Presence>,
+    /// Failed turns in a row, by player
+    failures: Vec<usize>,
+    /// 0 never disconnects anyone
+    disconnect_after: usize,
+    grace_turns: Option<usize>,
+}
+
+impl PresenceTracker {
+    pub fn new(num_players: usize, disconnect_after: usize, grace_turns: Option<usize>) -> Self {
+        PresenceTracker {
+            presence: vec![Presence::Connected; num_players],
+            failures: vec![0; num_players],
+            disconnect_after,
+            grace_turns,
+        }
+    }
+
+    /// Whether `player`'s bot should still be asked for moves
+    pub fn is_playing(&self, player: usize) -> bool {
+        self.presence[player] == Presence::Connected
+    }
+
+    /// Records how `player`'s turn went, and returns whether they just left the game
+    pub fn record(&mut self, player: usize, turn: usize, outcome: &Outcome) -> bool {
+        match outcome {
+            Outcome::Surrendered => {
+                self.presence[player] = Presence::Surrendered { turn };
+                true
+            }
+            Outcome::Failed { .. } => {
+                self.failures[player] += 1;
+                if self.disconnect_after > 0 && self.failures[player] >= self.disconnect_after {
+                    self.presence[player] = Presence::Disconnected { turn };
+                    true
+                } else {
+                    false
+                }
+            }
+            _ => {
+                self.failures[player] = 0;
+                false
+            }
+        }
+    }
+
+    /// Players whose spaces turn neutral on `turn`: those who surrendered on it, and those whose
+    /// grace period after disconnecting ends on it
+    pub fn abandoned(&self, turn: usize) -> Vec<usize> {
+        self.presence
+            .iter()
+            .enumerate()
+            .filter(|(_, presence)| match **presence {
+                Presence::Connected => false,
+                Presence::Disconnected { turn: since } => {
+                    self.grace_turns.is_some_and(|grace| since + grace == turn)
+                }
+                Presence::Surrendered { turn: since } => since == turn,
+            })
+            .map(|(player, _)| player)
+            .collect()
+    }
+
+    /// Indexed by player
+    pub fn presence(&self) -> &[Presence] {
+        &self.presence
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failed() -> Outcome {
+        Outcome::Failed {
+            error: "connection refused".to_string(),
+        }
+    }
+
+    #[test]
+    fn only_failures_in_a_row_disconnect() {
+        let mut tracker = PresenceTracker::new(2, 2, Some(3));
+
+        assert!(!tracker.record(0, 1, &failed()));
+        assert!(!tracker.record(0, 2, &Outcome::Passed));
+        assert!(!tracker.record(0, 3, &failed()));
+        assert!(tracker.record(0, 4, &failed()));
+        assert!(!tracker.is_playing(0));
+        assert_eq!(tracker.presence()[0], Presence::Disconnected { turn: 4 });
+
+        assert!(tracker.abandoned(6).is_empty());
+        assert_eq!(tracker.abandoned(7), vec![0]);
+    }
+
+    #[test]
+    fn surrendering_abandons_right_away() {
+        let mut tracker = PresenceTracker::new(2, 0, None);
+
+        for turn in 0..10 {
+            assert!(!tracker.record(1, turn, &failed()));
+        }
+        assert!(tracker.record(1, 10, &Outcome::Surrendered));
+        assert_eq!(tracker.abandoned(10), vec![1]);
+        assert!(tracker.is_playing(0));
+    }
+}
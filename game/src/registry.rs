@@ -0,0 +1,351 @@
+use std::{
+    error::Error,
+    fmt::Display,
+    io,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, RwLock,
+    },
+};
+
+use engine::{GameState, GenerationError};
+use model::PlayerInfo;
+use rand::Rng;
+use serde::Serialize;
+use tokio::sync::broadcast::{self, Sender};
+use tracing::instrument::WithSubscriber;
+
+use crate::{
+    ai::Ai,
+    control::{ControlState, GameControl},
+    logging::LogConfig,
+    metrics::Metrics,
+    runner::{run_game, GameConfig},
+    spectate::SpectatorUpdate,
+    stats::StatsHistory,
+    summary::GameSummary,
+};
+
+/// Games need someone to win against
+pub const MIN_PLAYERS: usize = 2;
+
+/// How many finished games stay in memory for `/games` and spectators. Older ones are only kept
+/// as replays.
+const KEPT_FINISHED_GAMES: usize = 20;
+
+/// A short random id for games and bots
+pub fn new_id() -> String {
+    format!("{:08x}", rand::thread_rng().gen::<u32>())
+}
+
+/// Who to seat in a new game. Anything left out gets a default.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PlayerSpec {
+    /// `hostname:port` or just `port`
+    pub endpoint: String,
+    pub name: Option<String>,
+    pub author: Option<String>,
+    pub color: Option<String>,
+}
+
+impl PlayerSpec {
+    /// `hostname:port` or `port`, optionally prefixed with `name@`
+    pub fn from_arg(arg: &str) -> Self {
+        match arg.split_once('@') {
+            Some((name, endpoint)) => PlayerSpec {
+                endpoint: endpoint.to_string(),
+                name: Some(name.to_string()),
+                ..PlayerSpec::default()
+            },
+            None => PlayerSpec {
+                endpoint: arg.to_string(),
+                ..PlayerSpec::default()
+            },
+        }
+    }
+
+    fn info(&self, seat: usize) -> PlayerInfo {
+        let unnamed = PlayerInfo::unnamed(seat);
+        PlayerInfo {
+            name: self.name.clone().unwrap_or(unnamed.name),
+            author: self.author.clone().unwrap_or(unnamed.author),
+            color: self.color.clone().unwrap_or(unnamed.color),
+        }
+    }
+}
+
+/// One game hosted by the server, running or finished
+#[derive(Debug)]
+pub struct Game {
+    pub id: String,
+    /// Indexed by seat
+    pub players: Vec<PlayerInfo>,
+    /// The address of each player's bot
+    pub endpoints: Vec<String>,
+    pub config: GameConfig,
+    pub control: GameControl,
+    /// Every update, numbered so spectators can tell which ones they've already seen
+    pub spectators: Sender<(u64, Arc<SpectatorUpdate>)>,
+    published: Mutex<Published>,
+    turn: AtomicUsize,
+    summary: Mutex<Option<GameSummary>>,
+}
+
+/// What spectators who join later are sent. Updates only carry their own turn's stats, so the
+/// rest of the history is kept here, behind the same lock so it always matches the latest update.
+#[derive(Debug, Default)]
+struct Published {
+    latest: Option<(u64, Arc<SpectatorUpdate>)>,
+    history: StatsHistory,
+}
+
+impl Game {
+    /// Sends an update to every spectator, and keeps it for anyone who joins later
+    pub fn publish(&self, update: SpectatorUpdate) {
+        let mut published = self.published.lock().unwrap();
+        // The last update is sent again with the summary once the game is over, without a new turn
+        if published
+            .latest
+            .as_ref()
+            .is_none_or(|(_, latest)| latest.state.turn != update.state.turn)
+        {
+            published.history.push(update.stats.clone());
+        }
+        let seq = published.latest.as_ref().map_or(0, |(seq, _)| seq + 1);
+        let update = (seq, Arc::new(update));
+        published.latest = Some(update.clone());
+        // Ignore errors because there might be no subcribers
+        let _ = self.spectators.send(update);
+    }
+
+    /// The latest update, with every turn's stats up to and including it
+    pub fn snapshot(&self) -> Option<(u64, Arc<SpectatorUpdate>, StatsHistory)> {
+        let published = self.published.lock().unwrap();
+        let (seq, update) = published.latest.clone()?;
+        Some((seq, update, published.history.clone()))
+    }
+
+    pub fn set_turn(&self, turn: usize) {
+        self.turn.store(turn, Ordering::Relaxed);
+    }
+
+    pub fn finish(&self, summary: GameSummary) {
+        *self.summary.lock().unwrap() = Some(summary);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.summary.lock().unwrap().is_some()
+    }
+
+    pub fn info(&self) -> GameInfo {
+        let summary = self.summary.lock().unwrap().clone();
+        GameInfo {
+            id: self.id.clone(),
+            players: self.players.clone(),
+            endpoints: self.endpoints.clone(),
+            turn: self.turn.load(Ordering::Relaxed),
+            finished: summary.is_some(),
+            control: self.control.state(),
+            summary,
+        }
+    }
+}
+
+/// What `/games` says about a game
+#[derive(Debug, Clone, Serialize)]
+pub struct GameInfo {
+    pub id: String,
+    pub players: Vec<PlayerInfo>,
+    pub endpoints: Vec<String>,
+    pub turn: usize,
+    pub finished: bool,
+    pub control: ControlState,
+    pub summary: Option<GameSummary>,
+}
+
+#[derive(Debug)]
+pub enum CreateGameError {
+    NotEnoughPlayers(usize),
+    InvalidPlayer(String),
+    Generation(GenerationError),
+    Logging(io::Error),
+}
+impl Display for CreateGameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CreateGameError::NotEnoughPlayers(count) => write!(
+                f,
+                "a game needs at least {MIN_PLAYERS} players, but got {count}"
+            ),
+            CreateGameError::InvalidPlayer(err) => f.write_str(err),
+            CreateGameError::Generation(err) => err.fmt(f),
+            CreateGameError::Logging(err) => {
+                write!(f, "couldn't create the game's log file: {err}")
+            }
+        }
+    }
+}
+impl Error for CreateGameError {}
+
+/// Drops all but the newest `keep` finished games, keeping the rest in order
+fn evict_finished<T>(games: &mut Vec<T>, is_finished: impl Fn(&T) -> bool, keep: usize) {
+    let mut to_drop = games
+        .iter()
+        .filter(|game| is_finished(game))
+        .count()
+        .saturating_sub(keep);
+    games.retain(|game| {
+        if to_drop > 0 && is_finished(game) {
+            to_drop -= 1;
+            false
+        } else {
+            true
+        }
+    });
+}
+
+/// Every running game, and the most recently finished ones
+#[derive(Debug)]
+pub struct GameRegistry {
+    /// In the order they were created
+    games: RwLock<Vec<Arc<Game>>>,
+    logs: LogConfig,
+    /// Where finished games are saved
+    replays: PathBuf,
+    metrics: Arc<Metrics>,
+}
+
+impl GameRegistry {
+    pub fn new(logs: LogConfig, replays: PathBuf, metrics: Arc<Metrics>) -> Self {
+        GameRegistry {
+            games: RwLock::new(vec![]),
+            logs,
+            replays,
+            metrics,
+        }
+    }
+
+    /// Sets up a game between `players` and starts playing it in the background
+    pub fn create(
+        &self,
+        players: &[PlayerSpec],
+        config: GameConfig,
+    ) -> Result<Arc<Game>, CreateGameError> {
+        if players.len() < MIN_PLAYERS {
+            return Err(CreateGameError::NotEnoughPlayers(players.len()));
+        }
+        let ais = players
+            .iter()
+            .map(|player| Ai::from_arg(&player.endpoint))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(CreateGameError::InvalidPlayer)?;
+        let game_state =
+            GameState::new(ais.len(), &config.towns).map_err(CreateGameError::Generation)?;
+
+        let id = new_id();
+        let dispatch = self
+            .logs
+            .game_dispatch(&id)
+            .map_err(CreateGameError::Logging)?;
+
+        let (spectators, _) = broadcast::channel(16);
+        let game = Arc::new(Game {
+            id,
+            players: players
+                .iter()
+                .enumerate()
+                .map(|(seat, player)| player.info(seat))
+                .collect(),
+            endpoints: ais.iter().map(|ai| ai.to_string()).collect(),
+            control: GameControl::new(ControlState {
+                tick_ms: config.tick_ms,
+                ..ControlState::default()
+            }),
+            config,
+            spectators,
+            published: Mutex::default(),
+            turn: AtomicUsize::new(0),
+            summary: Mutex::new(None),
+        });
+        {
+            let mut games = self.games.write().unwrap();
+            evict_finished(&mut games, |game| game.is_finished(), KEPT_FINISHED_GAMES);
+            games.push(game.clone());
+        }
+
+        let metrics = self.metrics.clone();
+        let replays = self.replays.clone();
+        let running = game.clone();
+        tokio::spawn(
+            async move { run_game(&running, &ais, game_state, &replays, &metrics).await }
+                .with_subscriber(dispatch),
+        );
+        Ok(game)
+    }
+
+    pub fn get(&self, id: &str) -> Option<Arc<Game>> {
+        self.games
+            .read()
+            .unwrap()
+            .iter()
+            .find(|game| game.id == id)
+            .cloned()
+    }
+
+    pub fn list(&self) -> Vec<GameInfo> {
+        self.games
+            .read()
+            .unwrap()
+            .iter()
+            .map(|game| game.info())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn player_args_can_be_named() {
+        assert_eq!(
+            PlayerSpec::from_arg("rando@localhost:8081"),
+            PlayerSpec {
+                endpoint: "localhost:8081".to_string(),
+                name: Some("rando".to_string()),
+                ..PlayerSpec::default()
+            }
+        );
+        assert_eq!(PlayerSpec::from_arg("8081").endpoint, "8081");
+        assert_eq!(PlayerSpec::from_arg("8081").info(2), PlayerInfo::unnamed(2));
+    }
+
+    #[test]
+    fn only_the_newest_finished_games_are_kept() {
+        // Odd numbers are finished games
+        let mut games: Vec<usize> = (0..8).collect();
+        evict_finished(&mut games, |game| game % 2 == 1, 2);
+        assert_eq!(games, vec![0, 2, 4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn games_need_at_least_two_players() {
+        let dir = tempfile::tempdir().expect("Can create a temporary directory");
+        let registry = GameRegistry::new(
+            LogConfig {
+                format: crate::logging::LogFormat::Text,
+                dir: dir.path().join("logs"),
+            },
+            dir.path().join("replays"),
+            Arc::new(Metrics::default()),
+        );
+        for players in [vec![], vec![PlayerSpec::from_arg("8081")]] {
+            assert!(matches!(
+                registry.create(&players, GameConfig::default()),
+                Err(CreateGameError::NotEnoughPlayers(count)) if count == players.len()
+            ));
+        }
+        assert!(registry.list().is_empty());
+    }
+}
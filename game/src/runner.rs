@@ -0,0 +1,569 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use engine::{GameState, TownConfig, TurnReport};
+use model::{
+    replay::{diff, Replay},
+    GameStart, TurnAction, TurnReply,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error, info, info_span, warn, Instrument};
+
+use crate::{
+    ai::Ai,
+    control::ControlState,
+    latency::{Budget, LatencyTracker, Penalty},
+    metrics::{rejection_reason, Metrics},
+    perspective::{Outcome, PlayerTurn},
+    presence::PresenceTracker,
+    registry::Game,
+    spectate::{SpectatorUpdate, TurnInfo},
+    stats::PlayerStats,
+    summary::GameSummary,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameConfig {
+    /// End the game without a winner after this many turns
+    pub max_turns: Option<usize>,
+    /// Responses slower than this are penalized
+    pub latency_budget_ms: Option<u64>,
+    pub latency_penalty: Penalty,
+    /// Time between turns when the game starts
+    pub tick_ms: u64,
+    /// Only show bots the spaces they own and the ones touching them
+    pub fog_of_war: bool,
+    /// How neutral towns are generated
+    pub towns: TownConfig,
+    /// Bots that take longer than this to answer have failed their turn
+    pub bot_timeout_ms: u64,
+    /// Bots that fail this many turns in a row are disconnected, and aren't asked for moves
+    /// anymore. 0 never disconnects anyone.
+    pub disconnect_after: usize,
+    /// Turns after disconnecting before a player's spaces turn neutral. Until then, or forever if
+    /// not set, they're left as they are.
+    pub disconnect_grace_turns: Option<usize>,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            max_turns: None,
+            latency_budget_ms: None,
+            latency_penalty: Penalty::Flag,
+            tick_ms: ControlState::default().tick_ms,
+            fog_of_war: false,
+            towns: TownConfig::default(),
+            bot_timeout_ms: 5000,
+            disconnect_after: 5,
+            disconnect_grace_turns: Some(20),
+        }
+    }
+}
+
+impl GameConfig {
+    /// The defaults for every game on this server, from `MAX_TURNS`, `LATENCY_BUDGET_MS`,
+    /// `LATENCY_PENALTY`, `TICK_MS`, `FOG_OF_WAR`, `TOWN_GARRISON`, `TOWN_GROWTH`, `BOT_TIMEOUT_MS`,
+    /// `DISCONNECT_AFTER` and `DISCONNECT_GRACE_TURNS`
+    pub fn from_env() -> Result<Self, String> {
+        let number = |name: &str| -> Result<Option<u64>, String> {
+            match std::env::var(name) {
+                Ok(value) => value
+                    .parse()
+                    .map(Some)
+                    .map_err(|_| format!("{name} should be a number, got '{value}'")),
+                Err(_) => Ok(None),
+            }
+        };
+        let defaults = GameConfig::default();
+        Ok(GameConfig {
+            max_turns: number("MAX_TURNS")?.map(|turns| turns as usize),
+            latency_budget_ms: number("LATENCY_BUDGET_MS")?,
+            latency_penalty: match std::env::var("LATENCY_PENALTY") {
+                Ok(penalty) => Penalty::from_arg(&penalty)?,
+                Err(_) => defaults.latency_penalty,
+            },
+            tick_ms: number("TICK_MS")?.unwrap_or(defaults.tick_ms),
+            fog_of_war: match std::env::var("FOG_OF_WAR") {
+                Ok(value) => value
+                    .parse()
+                    .map_err(|_| format!("FOG_OF_WAR should be true or false, got '{value}'"))?,
+                Err(_) => defaults.fog_of_war,
+            },
+            towns: towns_from_env(defaults.towns)?,
+            bot_timeout_ms: number("BOT_TIMEOUT_MS")?.unwrap_or(defaults.bot_timeout_ms),
+            disconnect_after: number("DISCONNECT_AFTER")?
+                .map_or(defaults.disconnect_after, |turns| turns as usize),
+            disconnect_grace_turns: number("DISCONNECT_GRACE_TURNS")?
+                .map(|turns| turns as usize)
+                .or(defaults.disconnect_grace_turns),
+        })
+    }
+
+    pub fn bot_timeout(&self) -> Duration {
+        Duration::from_millis(self.bot_timeout_ms)
+    }
+
+    pub fn budget(&self) -> Option<Budget> {
+        self.latency_budget_ms.map(|limit| Budget {
+            limit: Duration::from_millis(limit),
+            penalty: self.latency_penalty,
+        })
+    }
+}
+
+/// `TOWN_GARRISON` is a number like `50` or a range like `30-70`, and `TOWN_GROWTH` is a list like
+/// `1,1,2`
+fn towns_from_env(defaults: TownConfig) -> Result<TownConfig, String> {
+    let mut towns = defaults;
+    if let Ok(value) = std::env::var("TOWN_GARRISON") {
+        let error =
+            || format!("TOWN_GARRISON should be a number or a range like 30-70, got '{value}'");
+        let (min, max) = value.split_once('-').unwrap_or((&value, &value));
+        towns.min_garrison = min.trim().parse().map_err(|_| error())?;
+        towns.max_garrison = max.trim().parse().map_err(|_| error())?;
+    }
+    if let Ok(value) = std::env::var("TOWN_GROWTH") {
+        towns.growth = value
+            .split(',')
+            .map(|growth| growth.trim().parse())
+            .collect::<Result<_, _>>()
+            .map_err(|_| {
+                format!("TOWN_GROWTH should be a list of numbers like 1,1,2, got '{value}'")
+            })?;
+    }
+    towns.check().map_err(|err| err.to_string())?;
+    Ok(towns)
+}
+
+/// Plays a game to the end, or until it's aborted through its controls, sending every turn to its
+/// spectators. The replay is saved to `replays` afterwards.
+pub async fn run_game(
+    game: &Game,
+    players: &[Ai],
+    mut game_state: GameState,
+    replays: &Path,
+    metrics: &Arc<Metrics>,
+) -> GameSummary {
+    let config = &game.config;
+    let control = &game.control;
+    async {
+        let mut latency = LatencyTracker::new(players.len(), config.budget());
+        let mut presence = PresenceTracker::new(
+            players.len(),
+            config.disconnect_after,
+            config.disconnect_grace_turns,
+        );
+        let mut replay = Replay::new(game.id.clone(), game.players.clone(), game_state.spaces);
+        let mut stats = PlayerStats::after_turn(&game_state.spaces, &[], &vec![0; players.len()]);
+        metrics.game_started();
+        info!(players = ?game.players.iter().map(|p| &p.name).collect::<Vec<_>>(), "Game started");
+
+        for (i, ai) in players.iter().enumerate() {
+            let start = GameStart {
+                game: game.id.clone(),
+                player: i,
+                players: game.players.clone(),
+            };
+            if let Err(err) = ai.start_game(&start, config.bot_timeout()).await {
+                debug!(player = i, error = %err, "Bot didn't take the game start message");
+            }
+        }
+
+        let info = |latency: &LatencyTracker,
+                    presence: &PresenceTracker,
+                    last_turn: TurnReport,
+                    summary: Option<GameSummary>| TurnInfo {
+            players: game.players.clone(),
+            latency: latency.stats(),
+            control: control.state(),
+            last_turn,
+            presence: presence.presence().to_vec(),
+            summary,
+        };
+        game.publish(SpectatorUpdate {
+            state: game_state.clone(),
+            info: info(&latency, &presence, TurnReport::default(), None),
+            stats: stats.clone(),
+            perspectives: vec![],
+        });
+
+        let mut last_turn = TurnReport::default();
+        let mut perspectives = vec![];
+        let mut aborted = false;
+        while contenders(&game_state, &presence).len() > 1
+            && config.max_turns.is_none_or(|max| game_state.turn < max)
+        {
+            if !control.next_turn().await {
+                info!(turn = game_state.turn, "Game aborted");
+                aborted = true;
+                break;
+            }
+
+            let turn = game_state.turn;
+            let before = game_state.spaces;
+            let (turns, report) = play_turn(
+                players,
+                &mut game_state,
+                config,
+                &mut latency,
+                &mut presence,
+                &game.id,
+                metrics,
+            )
+            .instrument(info_span!("turn", turn))
+            .await;
+            game.set_turn(game_state.turn);
+            replay.turns.push(diff(&before, &game_state.spaces));
+            let rejected: Vec<usize> = turns
+                .iter()
+                .map(|turn| usize::from(turn.outcome.is_rejected()))
+                .collect();
+            stats = PlayerStats::after_turn(&game_state.spaces, &stats, &rejected);
+            last_turn = report;
+            perspectives = turns;
+
+            game.publish(SpectatorUpdate {
+                state: game_state.clone(),
+                info: info(&latency, &presence, last_turn.clone(), None),
+                stats: stats.clone(),
+                perspectives: perspectives.clone(),
+            });
+
+            control.tick().await;
+        }
+
+        metrics.game_ended();
+
+        let summary = GameSummary {
+            players: game.players.clone(),
+            winner: winner(&game_state, &presence),
+            turns: game_state.turn,
+            aborted,
+            latency: latency.stats(),
+            stats: stats.clone(),
+        };
+        info!(winner = ?summary.winner, turns = summary.turns, aborted, "Game over");
+        for (player, stats) in summary.latency.iter().enumerate() {
+            info!(
+                player,
+                name = game.players[player].name,
+                requests = stats.requests,
+                p50_ms = stats.p50_ms,
+                p95_ms = stats.p95_ms,
+                max_ms = stats.max_ms,
+                over_budget = stats.over_budget,
+                "Response times"
+            );
+        }
+
+        replay.winner = summary.winner;
+        match save_replay(&replay, replays) {
+            Ok(path) => info!(path = %path.display(), "Saved replay"),
+            Err(err) => error!(error = %err, "Couldn't save replay"),
+        }
+
+        game.finish(summary.clone());
+        game.publish(SpectatorUpdate {
+            state: game_state,
+            info: info(&latency, &presence, last_turn, Some(summary.clone())),
+            stats,
+            perspectives,
+        });
+        summary
+    }
+    .instrument(info_span!("game", id = game.id))
+    .await
+}
+
+/// Players who still hold land and whose bots are still playing. Once there's at most one left,
+/// nobody else can change how the game ends, so it's over.
+fn contenders(game_state: &GameState, presence: &PresenceTracker) -> Vec<usize> {
+    game_state
+        .alive_players()
+        .into_iter()
+        .filter(|player| presence.is_playing(*player))
+        .collect()
+}
+
+/// The last player standing, or else the last one still playing once everyone else has left
+fn winner(game_state: &GameState, presence: &PresenceTracker) -> Option<usize> {
+    game_state
+        .winner()
+        .or_else(|| match contenders(game_state, presence)[..] {
+            [player] => Some(player),
+            _ => None,
+        })
+}
+
+fn save_replay(replay: &Replay, dir: &Path) -> io::Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    let path = dir.join(format!("{}.json", replay.id));
+    fs::write(&path, serde_json::to_string(replay)?)?;
+    Ok(path)
+}
+
+/// Asks every bot that's still playing for a move and plays them, after taking away the spaces of
+/// players that just left the game. Returns what each player was asked and answered, and what
+/// happened to the moves that were played.
+async fn play_turn(
+    players: &[Ai],
+    game_state: &mut GameState,
+    config: &GameConfig,
+    latency: &mut LatencyTracker,
+    presence: &mut PresenceTracker,
+    game_id: &str,
+    metrics: &Metrics,
+) -> (Vec<PlayerTurn>, TurnReport) {
+    let turn_start = Instant::now();
+    let mut moves = vec![];
+    let mut turns = vec![];
+
+    for (i, ai) in players.iter().enumerate() {
+        let mut turn = PlayerTurn::new(game_state.turn, i, &game_state.spaces, config.fog_of_war);
+        if !presence.is_playing(i) {
+            turn.outcome = Outcome::Absent;
+            turns.push(turn);
+            continue;
+        }
+        let start = Instant::now();
+        let result = ai.make_move(&turn.request, config.bot_timeout()).await;
+        let elapsed = start.elapsed();
+        let elapsed_ms = elapsed.as_millis() as u64;
+        turn.elapsed_ms = elapsed_ms;
+        turn.response = match result {
+            Ok(Some(TurnReply::Move(response))) => Some(response),
+            _ => None,
+        };
+        metrics.bot_responded(game_id, i, elapsed);
+        // Only late moves are thrown away. Failures and surrenders count no matter how long they
+        // took.
+        let skip_late_move = latency.record(i, elapsed);
+        if !skip_late_move
+            && latency
+                .budget()
+                .is_some_and(|budget| elapsed > budget.limit)
+        {
+            warn!(
+                player = i,
+                elapsed_ms, "Response was over the latency budget"
+            );
+        }
+
+        turn.outcome = match result {
+            Ok(Some(TurnReply::Move(_))) if skip_late_move => {
+                metrics.move_rejected("over_budget");
+                warn!(
+                    player = i,
+                    elapsed_ms, "Response was over the latency budget, so its move was skipped"
+                );
+                Outcome::OverBudget
+            }
+            Ok(Some(TurnReply::Move(response))) => {
+                debug!(player = i, elapsed_ms, ?response, "Bot responded");
+                match game_state.validate_move(i, response.from, response.to) {
+                    Ok(m) => {
+                        moves.push(m);
+                        Outcome::Accepted
+                    }
+                    Err(err) => {
+                        let reason = rejection_reason(&err);
+                        metrics.move_rejected(reason);
+                        warn!(player = i, reason, ?response, "Invalid move: {err}");
+                        Outcome::Rejected {
+                            reason: err.to_string(),
+                        }
+                    }
+                }
+            }
+            Ok(Some(TurnReply::Action(TurnAction::Surrender))) => {
+                info!(player = i, "Bot surrendered");
+                Outcome::Surrendered
+            }
+            Ok(None) => {
+                debug!(player = i, elapsed_ms, "Bot passed");
+                Outcome::Passed
+            }
+            Err(err) => {
+                metrics.bot_failed(game_id, i);
+                warn!(player = i, elapsed_ms, error = %err, "Bot failed to respond");
+                Outcome::Failed {
+                    error: err.to_string(),
+                }
+            }
+        };
+        if presence.record(i, game_state.turn, &turn.outcome)
+            && turn.outcome != Outcome::Surrendered
+        {
+            warn!(
+                player = i,
+                failures = config.disconnect_after,
+                "Bot disconnected after failing too many turns in a row"
+            );
+        }
+        turns.push(turn);
+    }
+
+    for player in presence.abandoned(game_state.turn) {
+        info!(player, "Player's spaces turned neutral");
+        game_state.neutralize(player);
+    }
+    let report = game_state.step(moves).unwrap_or_else(|err| {
+        error!(error = %err, "Skipping moves for this turn");
+        game_state.end_turn();
+        TurnReport::default()
+    });
+    metrics.turn_processed(turn_start.elapsed());
+    (turns, report)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use model::{Coordinate, Space};
+
+    use super::*;
+    use crate::{
+        latency::Penalty,
+        logging::{LogConfig, LogFormat},
+        presence::Presence,
+        registry::{GameRegistry, PlayerSpec},
+    };
+
+    /// The address of a port nothing is listening on
+    fn closed_port() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Can bind a free port");
+        format!("127.0.0.1:{}", listener.local_addr().unwrap().port())
+    }
+
+    #[test]
+    fn players_who_left_can_still_win_or_lose() {
+        let mut game_state = GameState {
+            spaces: Default::default(),
+            turn: 0,
+        };
+        game_state.spaces[Coordinate { x: 0, y: 0 }] = Space::PlayerCapital { owner: 0, units: 1 };
+        game_state.spaces[Coordinate { x: 5, y: 5 }] = Space::PlayerCapital { owner: 1, units: 1 };
+        let mut presence = PresenceTracker::new(3, 1, None);
+        assert_eq!(contenders(&game_state, &presence), vec![0, 1]);
+        assert_eq!(winner(&game_state, &presence), None);
+
+        presence.record(1, 0, &Outcome::Surrendered);
+        assert_eq!(contenders(&game_state, &presence), vec![0]);
+        assert_eq!(winner(&game_state, &presence), Some(0));
+
+        presence.record(0, 0, &Outcome::Surrendered);
+        assert!(contenders(&game_state, &presence).is_empty());
+        assert_eq!(winner(&game_state, &presence), None);
+    }
+
+    fn registry(dir: &Path) -> GameRegistry {
+        GameRegistry::new(
+            LogConfig {
+                format: LogFormat::Text,
+                dir: dir.join("logs"),
+            },
+            dir.join("replays"),
+            Arc::new(Metrics::default()),
+        )
+    }
+
+    async fn summary_once_finished(game: &Game) -> GameSummary {
+        tokio::time::timeout(Duration::from_secs(2), async {
+            while !game.is_finished() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("Game ends once every bot has disconnected");
+        game.info().summary.expect("Game is finished")
+    }
+
+    #[tokio::test]
+    async fn game_ends_without_a_winner_when_every_bot_fails() {
+        let dir = tempfile::tempdir().expect("Can create a temporary directory");
+        let registry = registry(dir.path());
+        let players = [closed_port(), closed_port()].map(|port| PlayerSpec::from_arg(&port));
+        let game = registry
+            .create(
+                &players,
+                GameConfig {
+                    tick_ms: 0,
+                    bot_timeout_ms: 100,
+                    disconnect_after: 1,
+                    disconnect_grace_turns: None,
+                    ..GameConfig::default()
+                },
+            )
+            .expect("Game is valid");
+
+        let summary = summary_once_finished(&game).await;
+        assert_eq!(summary.winner, None);
+        assert_eq!(summary.turns, 1);
+        assert!(!summary.aborted);
+        assert!(dir
+            .path()
+            .join("replays")
+            .join(format!("{}.json", game.id))
+            .exists());
+    }
+
+    #[tokio::test]
+    async fn bots_that_time_out_are_disconnected_even_when_late_moves_are_skipped() {
+        let dir = tempfile::tempdir().expect("Can create a temporary directory");
+        let registry = registry(dir.path());
+        // Connections wait in the backlog without ever being answered
+        let hanging = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Can bind a free port");
+        let address = hanging.local_addr().unwrap().to_string();
+        let players = [
+            PlayerSpec::from_arg(&address),
+            PlayerSpec::from_arg(&address),
+        ];
+        let game = registry
+            .create(
+                &players,
+                GameConfig {
+                    max_turns: Some(10),
+                    latency_budget_ms: Some(10),
+                    latency_penalty: Penalty::Skip,
+                    tick_ms: 0,
+                    bot_timeout_ms: 50,
+                    disconnect_after: 2,
+                    disconnect_grace_turns: None,
+                    ..GameConfig::default()
+                },
+            )
+            .expect("Game is valid");
+
+        let summary = summary_once_finished(&game).await;
+        assert_eq!(summary.winner, None);
+        assert_eq!(summary.turns, 2);
+        assert!(summary.latency.iter().all(|stats| stats.over_budget == 2));
+        let (_, update, history) = game.snapshot().expect("Game was published");
+        // The starting board and both turns, without the update repeated with the summary
+        assert_eq!(
+            serde_json::to_value(&history)
+                .unwrap()
+                .as_array()
+                .unwrap()
+                .len(),
+            3
+        );
+        assert!(update
+            .perspectives
+            .iter()
+            .all(|turn| matches!(turn.outcome, Outcome::Failed { .. })));
+        assert_eq!(
+            update.info.presence,
+            vec![Presence::Disconnected { turn: 1 }; 2]
+        );
+    }
+}
@@ -0,0 +1,247 @@
+use std::sync::Arc;
+
+use axum::extract::ws::{Message, WebSocket};
+use engine::{GameState, TurnReport};
+use model::{
+    replay::{diff, TileChange},
+    Board, PlayerInfo,
+};
+use serde::Serialize;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{debug, info};
+
+use crate::{
+    control::ControlState,
+    latency::LatencyStats,
+    metrics::SpectatorGuard,
+    perspective::PlayerTurn,
+    presence::Presence,
+    registry::Game,
+    stats::{PlayerStats, StatsHistory},
+    summary::GameSummary,
+};
+
+/// Everything spectators are told about a game after a turn
+#[derive(Debug, Clone, Serialize)]
+pub struct SpectatorUpdate {
+    pub state: GameState,
+    pub info: TurnInfo,
+    /// This turn's stats, indexed by player. The game keeps the earlier turns' for snapshots.
+    pub stats: Vec<PlayerStats>,
+    /// What each player was sent and answered in the turn that just ended, indexed by player
+    pub perspectives: Vec<PlayerTurn>,
+}
+
+/// The parts of an update that are sent in full every turn
+#[derive(Debug, Clone, Serialize)]
+pub struct TurnInfo {
+    /// Indexed by seat
+    pub players: Vec<PlayerInfo>,
+    /// Indexed by player
+    pub latency: Vec<LatencyStats>,
+    pub control: ControlState,
+    /// The moves played and battles fought in the turn that just ended
+    pub last_turn: TurnReport,
+    /// Which players are still playing, indexed by player
+    pub presence: Vec<Presence>,
+    /// Only sent once the game is over
+    pub summary: Option<GameSummary>,
+}
+
+/// What's sent over a spectator's websocket. The first message is always a snapshot, and so is the
+/// message after the spectator falls behind. Everything else only has the spaces that changed, and
+/// only this turn's stats. Spectators watching from one player's perspective also get what that
+/// player's bot was sent and answered.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum SpectatorMessage<'a> {
+    Snapshot {
+        #[serde(flatten)]
+        state: &'a GameState,
+        #[serde(flatten)]
+        info: &'a TurnInfo,
+        /// Every turn's stats so far
+        history: &'a StatsHistory,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        perspective: Option<&'a PlayerTurn>,
+    },
+    Diff {
+        turn: usize,
+        changes: Vec<TileChange>,
+        /// Indexed by player
+        stats: &'a [PlayerStats],
+        #[serde(flatten)]
+        info: &'a TurnInfo,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        perspective: Option<&'a PlayerTurn>,
+    },
+}
+
+impl<'a> SpectatorMessage<'a> {
+    pub fn snapshot(
+        update: &'a SpectatorUpdate,
+        history: &'a StatsHistory,
+        player: Option<usize>,
+    ) -> Self {
+        SpectatorMessage::Snapshot {
+            state: &update.state,
+            info: &update.info,
+            history,
+            perspective: player.and_then(|player| update.perspectives.get(player)),
+        }
+    }
+
+    pub fn diff(previous: &Board, update: &'a SpectatorUpdate, player: Option<usize>) -> Self {
+        SpectatorMessage::Diff {
+            turn: update.state.turn,
+            changes: diff(previous, &update.state.spaces),
+            stats: &update.stats,
+            info: &update.info,
+            perspective: player.and_then(|player| update.perspectives.get(player)),
+        }
+    }
+}
+
+/// Actual websocket statemachine (one will be spawned per connection). Streams `game` until it's
+/// over, then closes the socket. `player` picks whose perspective to include, if anyone's.
+pub async fn handle_socket(
+    mut socket: WebSocket,
+    game: Arc<Game>,
+    player: Option<usize>,
+    _guard: SpectatorGuard,
+) {
+    // Subscribe before taking the snapshot so no turn falls in between. Anything the snapshot
+    // already covers is skipped by its sequence number.
+    let mut reciever = game.spectators.subscribe();
+    let mut last_sent: Option<(u64, Arc<SpectatorUpdate>)> = None;
+    let mut resync = true;
+
+    loop {
+        let (seq, update, history) = if resync {
+            resync = false;
+            match game.snapshot() {
+                Some((seq, update, history)) => (seq, update, Some(history)),
+                // Nothing has been published yet, so wait for the first update
+                None => continue,
+            }
+        } else {
+            match reciever.recv().await {
+                // Snapshots need the stats history, which only the game has
+                Ok(_) if last_sent.is_none() => {
+                    resync = true;
+                    continue;
+                }
+                Ok((seq, update)) => (seq, update, None),
+                Err(RecvError::Lagged(missed)) => {
+                    debug!(id = game.id, missed, "Spectator fell behind, resyncing");
+                    resync = true;
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            }
+        };
+        if last_sent
+            .as_ref()
+            .is_some_and(|(last_seq, _)| *last_seq >= seq)
+        {
+            continue;
+        }
+
+        let message = match &history {
+            Some(history) => SpectatorMessage::snapshot(&update, history, player),
+            None => {
+                let (_, previous) = last_sent.as_ref().expect("Diffs only follow a snapshot");
+                SpectatorMessage::diff(&previous.state.spaces, &update, player)
+            }
+        };
+        if socket
+            .send(Message::Text(serde_json::to_string(&message).unwrap()))
+            .await
+            .is_err()
+        {
+            info!(id = game.id, "Unable to send ws message, closing socket");
+            return;
+        }
+
+        let finished = update.info.summary.is_some();
+        last_sent = Some((seq, update));
+        if finished {
+            break;
+        }
+    }
+
+    let _ = socket.send(Message::Close(None)).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn update(state: GameState) -> SpectatorUpdate {
+        let stats = PlayerStats::after_turn(&state.spaces, &[], &[0]);
+        SpectatorUpdate {
+            state,
+            info: TurnInfo {
+                players: vec![PlayerInfo::unnamed(0)],
+                latency: vec![],
+                control: ControlState::default(),
+                last_turn: TurnReport::default(),
+                presence: vec![Presence::Connected],
+                summary: None,
+            },
+            stats,
+            perspectives: vec![],
+        }
+    }
+
+    #[test]
+    fn diffs_only_have_changed_spaces() {
+        let before = GameState {
+            spaces: Board::default(),
+            turn: 3,
+        };
+        let mut after = update(GameState {
+            spaces: Board::default(),
+            turn: 4,
+        });
+        let c = model::Coordinate { x: 2, y: 5 };
+        after.state.spaces[c] = model::Space::PlayerEmpty { owner: 1, units: 7 };
+
+        let message =
+            serde_json::to_value(SpectatorMessage::diff(&before.spaces, &after, None)).unwrap();
+        assert_eq!(message["type"], "diff");
+        assert_eq!(message["turn"], 4);
+        assert_eq!(
+            message["changes"],
+            json!([{ "x": 2, "y": 5, "space": { "type": "PlayerEmpty", "owner": 1, "units": 7 } }])
+        );
+        assert_eq!(message["control"]["paused"], false);
+        assert_eq!(message["stats"][0]["land"], 0);
+        assert!(message.get("history").is_none());
+        assert!(message.get("perspective").is_none());
+        assert!(message.get("spaces").is_none());
+    }
+
+    #[test]
+    fn snapshots_have_the_whole_board() {
+        let update = update(GameState {
+            spaces: Board::default(),
+            turn: 0,
+        });
+
+        let mut history = StatsHistory::default();
+        history.push(update.stats.clone());
+        let message =
+            serde_json::to_value(SpectatorMessage::snapshot(&update, &history, None)).unwrap();
+        assert_eq!(message["type"], "snapshot");
+        assert_eq!(message["turn"], 0);
+        assert_eq!(
+            message["spaces"].as_array().unwrap().len(),
+            model::BOARD_SIZE
+        );
+        assert!(message["summary"].is_null());
+        assert_eq!(message["players"][0]["name"], "Player 0");
+        assert_eq!(message["history"].as_array().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,105 @@
+use model::{analysis::player_totals, Board};
+use serde::Serialize;
+
+/// Where one player stood at the end of a turn
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct PlayerStats {
+    pub land: usize,
+    pub units: usize,
+    /// Including their capital
+    pub towns: usize,
+    /// Moves rejected so far in the game, including ones skipped for being over the latency budget
+    pub rejected: usize,
+}
+
+/// Every player's stats for every turn of a game
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(transparent)]
+pub struct StatsHistory {
+    /// Indexed by turn, then by player
+    turns: Vec<Vec<PlayerStats>>,
+}
+
+impl PlayerStats {
+    /// Every player's stats after a turn, given the turn before (empty for the first one) and how
+    /// many moves each player had rejected during it
+    pub fn after_turn(board: &Board, previous: &[PlayerStats], rejected: &[usize]) -> Vec<Self> {
+        let totals = player_totals(board);
+        rejected
+            .iter()
+            .enumerate()
+            .map(|(player, rejected)| {
+                let totals = totals.get(&player).copied().unwrap_or_default();
+                PlayerStats {
+                    land: totals.land,
+                    units: totals.units,
+                    towns: totals.towns,
+                    rejected: previous.get(player).map_or(0, |previous| previous.rejected)
+                        + rejected,
+                }
+            })
+            .collect()
+    }
+}
+
+impl StatsHistory {
+    /// Adds the next turn, indexed by player
+    pub fn push(&mut self, stats: Vec<PlayerStats>) {
+        self.turns.push(stats);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use model::{Coordinate, Space};
+
+    #[test]
+    fn rejections_add_up_over_turns() {
+        let mut board = Board::default();
+        board[Coordinate { x: 0, y: 0 }] = Space::PlayerCapital { owner: 0, units: 5 };
+        board[Coordinate { x: 0, y: 1 }] = Space::PlayerEmpty { owner: 0, units: 2 };
+        board[Coordinate { x: 9, y: 9 }] = Space::PlayerTown {
+            owner: 1,
+            units: 4,
+            growth: 1,
+        };
+
+        let stats = [[0, 0], [1, 0], [2, 0]]
+            .iter()
+            .fold(vec![], |previous, rejected| {
+                PlayerStats::after_turn(&board, &previous, rejected)
+            });
+
+        assert_eq!(
+            stats,
+            [
+                PlayerStats {
+                    land: 2,
+                    units: 7,
+                    towns: 1,
+                    rejected: 3
+                },
+                PlayerStats {
+                    land: 1,
+                    units: 4,
+                    towns: 1,
+                    rejected: 0
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn eliminated_players_have_nothing() {
+        let stats = PlayerStats::after_turn(&Board::default(), &[], &[1, 0]);
+        assert_eq!(
+            stats[0],
+            PlayerStats {
+                rejected: 1,
+                ..PlayerStats::default()
+            }
+        );
+        assert_eq!(stats[1], PlayerStats::default());
+    }
+}
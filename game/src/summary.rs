@@ -0,0 +1,19 @@
+use model::PlayerInfo;
+use serde::Serialize;
+
+use crate::{latency::LatencyStats, stats::PlayerStats};
+
+/// How a game went, reported once it's over
+#[derive(Debug, Clone, Serialize)]
+pub struct GameSummary {
+    /// Indexed by seat
+    pub players: Vec<PlayerInfo>,
+    pub winner: Option<usize>,
+    pub turns: usize,
+    /// Whether the game was stopped early through the control API
+    pub aborted: bool,
+    /// Indexed by player
+    pub latency: Vec<LatencyStats>,
+    /// Where each player finished, indexed by player
+    pub stats: Vec<PlayerStats>,
+}
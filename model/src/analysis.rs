@@ -0,0 +1,243 @@
+//! Questions bots commonly ask about a board, answered the same way the server sees it.
+
+use std::collections::BTreeMap;
+
+use crate::{pathfinding::DistanceMap, Board, Coordinate, Space, TurnResponse};
+
+/**
+ * Every move `player` can make that actually moves units: from one of their spaces with units onto
+ * a passable neighbor
+ */
+pub fn legal_moves(board: &Board, player: usize) -> Vec<TurnResponse> {
+    board
+        .owned_by(player)
+        .filter(|from| board[*from].get_units() > 0)
+        .flat_map(|from| {
+            board
+                .passable_neighbors(from)
+                .map(move |to| TurnResponse { from, to })
+        })
+        .collect()
+}
+
+/**
+ * Passable spaces `player` doesn't own that touch one of their spaces, i.e. where they can expand
+ */
+pub fn frontier(board: &Board, player: usize) -> Vec<Coordinate> {
+    Board::coordinates()
+        .filter(|c| board[*c].is_passable() && board[*c].owner() != Some(player))
+        .filter(|c| {
+            c.surrounding()
+                .into_iter()
+                .any(|neighbor| board[neighbor].owner() == Some(player))
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PlayerTotals {
+    pub land: usize,
+    pub units: usize,
+    pub towns: usize,
+}
+
+/**
+ * Land, units and towns (including capitals) held by every player that owns at least one space
+ */
+pub fn player_totals(board: &Board) -> BTreeMap<usize, PlayerTotals> {
+    let mut totals: BTreeMap<usize, PlayerTotals> = BTreeMap::new();
+    for (_, space) in board.iter() {
+        if let Some(owner) = space.owner() {
+            let totals = totals.entry(owner).or_default();
+            totals.land += 1;
+            totals.units += space.get_units();
+            if matches!(
+                space,
+                Space::PlayerTown { .. } | Space::PlayerCapital { .. }
+            ) {
+                totals.towns += 1;
+            }
+        }
+    }
+    totals
+}
+
+pub fn capital(board: &Board, player: usize) -> Option<Coordinate> {
+    board
+        .owned_by(player)
+        .find(|c| matches!(board[*c], Space::PlayerCapital { .. }))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Target {
+    pub coordinate: Coordinate,
+    /// Moves needed from the closest space `player` owns
+    pub distance: usize,
+    pub units: usize,
+}
+
+/**
+ * Towns and capitals `player` doesn't own but could walk to, closest first
+ */
+pub fn reachable_towns(board: &Board, player: usize) -> Vec<Target> {
+    let distances = DistanceMap::from_origins(board, board.owned_by(player));
+    let mut towns: Vec<Target> = board
+        .iter()
+        .filter(|(_, space)| {
+            space.owner() != Some(player)
+                && matches!(
+                    space,
+                    Space::NeutralTown { .. }
+                        | Space::PlayerTown { .. }
+                        | Space::PlayerCapital { .. }
+                )
+        })
+        .filter_map(|(coordinate, space)| {
+            Some(Target {
+                coordinate,
+                distance: distances.distance(coordinate)?,
+                units: space.get_units(),
+            })
+        })
+        .collect();
+    towns.sort_by_key(|town| (town.distance, town.coordinate));
+    towns
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Threat {
+    pub coordinate: Coordinate,
+    pub owner: usize,
+    pub units: usize,
+    /// Moves needed to reach the threatened space
+    pub distance: usize,
+}
+
+/**
+ * Enemy armies that could reach `target` within `within` moves, closest first
+ */
+pub fn threats_to(board: &Board, target: Coordinate, player: usize, within: usize) -> Vec<Threat> {
+    let distances = DistanceMap::new(board, target);
+    let mut threats: Vec<Threat> = board
+        .iter()
+        .filter_map(|(coordinate, space)| {
+            let owner = space.owner().filter(|owner| *owner != player)?;
+            let distance = distances.distance(coordinate).filter(|d| *d <= within)?;
+            (space.get_units() > 0).then_some(Threat {
+                coordinate,
+                owner,
+                units: space.get_units(),
+                distance,
+            })
+        })
+        .collect();
+    threats.sort_by_key(|threat| (threat.distance, threat.coordinate));
+    threats
+}
+
+/**
+ * Enemy armies within `within` moves of `player`'s capital. Empty if they've lost their capital.
+ */
+pub fn capital_threats(board: &Board, player: usize, within: usize) -> Vec<Threat> {
+    capital(board, player)
+        .map(|capital| threats_to(board, capital, player, within))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn c(x: usize, y: usize) -> Coordinate {
+        Coordinate { x, y }
+    }
+
+    fn sample_board() -> Board {
+        let mut board = Board::default();
+        board[c(0, 0)] = Space::PlayerCapital { owner: 0, units: 4 };
+        board[c(1, 0)] = Space::PlayerEmpty { owner: 0, units: 0 };
+        board[c(0, 1)] = Space::Mountain;
+        board[c(3, 0)] = Space::PlayerEmpty { owner: 1, units: 6 };
+        board[c(9, 9)] = Space::PlayerTown {
+            owner: 1,
+            units: 2,
+            growth: 1,
+        };
+        board[c(2, 2)] = Space::NeutralTown {
+            units: 50,
+            growth: 1,
+            garrison: 50,
+        };
+        board
+    }
+
+    #[test]
+    fn legal_moves_only_move_units_onto_passable_spaces() {
+        let board = sample_board();
+
+        assert_eq!(
+            legal_moves(&board, 0),
+            vec![TurnResponse {
+                from: c(0, 0),
+                to: c(1, 0)
+            }]
+        );
+    }
+
+    #[test]
+    fn frontier_is_outside_player_territory() {
+        assert_eq!(frontier(&sample_board(), 0), vec![c(1, 1), c(2, 0)]);
+    }
+
+    #[test]
+    fn totals_count_land_units_and_towns() {
+        let totals = player_totals(&sample_board());
+
+        assert_eq!(
+            totals[&0],
+            PlayerTotals {
+                land: 2,
+                units: 4,
+                towns: 1
+            }
+        );
+        assert_eq!(
+            totals[&1],
+            PlayerTotals {
+                land: 2,
+                units: 8,
+                towns: 1
+            }
+        );
+    }
+
+    #[test]
+    fn reachable_towns_are_closest_first() {
+        let towns = reachable_towns(&sample_board(), 0);
+
+        assert_eq!(
+            towns
+                .iter()
+                .map(|t| (t.coordinate, t.distance))
+                .collect::<Vec<_>>(),
+            vec![(c(2, 2), 3), (c(9, 9), 17)]
+        );
+    }
+
+    #[test]
+    fn capital_threats_respect_range() {
+        let board = sample_board();
+
+        assert!(capital_threats(&board, 0, 2).is_empty());
+        assert_eq!(
+            capital_threats(&board, 0, 3),
+            vec![Threat {
+                coordinate: c(3, 0),
+                owner: 1,
+                units: 6,
+                distance: 3
+            }]
+        );
+        assert!(capital_threats(&board, 2, 20).is_empty());
+    }
+}
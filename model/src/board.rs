@@ -0,0 +1,187 @@
+use std::ops::{Index, IndexMut};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Coordinate, Space, Spaces, BOARD_SIZE, LOOKOUT_VISION};
+
+/// The spaces of a game, indexed by [`Coordinate`]. Serializes the same way as [`Spaces`], as a
+/// list of columns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Board(Spaces);
+
+impl Board {
+    /**
+     * Every coordinate on the board, column by column
+     */
+    pub fn coordinates() -> impl Iterator<Item = Coordinate> {
+        (0..BOARD_SIZE).flat_map(|x| (0..BOARD_SIZE).map(move |y| Coordinate { x, y }))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Coordinate, Space)> + '_ {
+        Self::coordinates().map(|c| (c, self[c]))
+    }
+
+    pub fn owned_by(&self, player: usize) -> impl Iterator<Item = Coordinate> + '_ {
+        Self::coordinates().filter(move |c| self[*c].owner() == Some(player))
+    }
+
+    /**
+     * Spaces owned by `player` that touch a passable space they don't own
+     */
+    pub fn border(&self, player: usize) -> impl Iterator<Item = Coordinate> + '_ {
+        self.owned_by(player).filter(move |c| {
+            self.passable_neighbors(*c)
+                .any(|neighbor| self[neighbor].owner() != Some(player))
+        })
+    }
+
+    /**
+     * Neighbors of `coordinate` that armies can move onto
+     */
+    pub fn passable_neighbors(
+        &self,
+        coordinate: Coordinate,
+    ) -> impl Iterator<Item = Coordinate> + '_ {
+        coordinate
+            .surrounding()
+            .into_iter()
+            .filter(|c| self[*c].is_passable())
+    }
+
+    /**
+     * Whether `player` can see `coordinate` under fog of war: they own it or a space touching it,
+     * diagonals included, or a lookout within [`LOOKOUT_VISION`] spaces of it
+     */
+    pub fn is_visible_to(&self, player: usize, coordinate: Coordinate) -> bool {
+        let xs = coordinate.x.saturating_sub(LOOKOUT_VISION)
+            ..=(coordinate.x + LOOKOUT_VISION).min(BOARD_SIZE - 1);
+        xs.flat_map(|x| {
+            let ys = coordinate.y.saturating_sub(LOOKOUT_VISION)
+                ..=(coordinate.y + LOOKOUT_VISION).min(BOARD_SIZE - 1);
+            ys.map(move |y| Coordinate { x, y })
+        })
+        .any(|c| {
+            self[c].owner() == Some(player)
+                && c.x.abs_diff(coordinate.x).max(c.y.abs_diff(coordinate.y)) <= self[c].vision()
+        })
+    }
+
+    /**
+     * The board as `player` sees it under fog of war. Spaces they can't see look empty, except
+     * mountains, which are always visible.
+     */
+    pub fn fogged(&self, player: usize) -> Board {
+        let mut fogged = *self;
+        for c in Self::coordinates() {
+            if self[c] != Space::Mountain && !self.is_visible_to(player, c) {
+                fogged[c] = Space::Empty;
+            }
+        }
+        fogged
+    }
+
+    pub fn spaces(&self) -> &Spaces {
+        &self.0
+    }
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Board([[Space::Empty; BOARD_SIZE]; BOARD_SIZE])
+    }
+}
+
+impl From<Spaces> for Board {
+    fn from(spaces: Spaces) -> Self {
+        Board(spaces)
+    }
+}
+
+impl Index<Coordinate> for Board {
+    type Output = Space;
+
+    fn index(&self, index: Coordinate) -> &Self::Output {
+        &self.0[index.x][index.y]
+    }
+}
+
+impl IndexMut<Coordinate> for Board {
+    fn index_mut(&mut self, index: Coordinate) -> &mut Self::Output {
+        &mut self.0[index.x][index.y]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_like_spaces() {
+        let mut board = Board::default();
+        board[Coordinate { x: 3, y: 1 }] = Space::PlayerTown {
+            owner: 2,
+            units: 7,
+            growth: 1,
+        };
+
+        let json = serde_json::to_string(&board).unwrap();
+        assert_eq!(json, serde_json::to_string(board.spaces()).unwrap());
+        assert_eq!(serde_json::from_str::<Board>(&json).unwrap(), board);
+    }
+
+    #[test]
+    fn border_only_includes_spaces_touching_other_land() {
+        let mut board = Board::default();
+        for x in 0..BOARD_SIZE {
+            for y in 0..BOARD_SIZE {
+                board[Coordinate { x, y }] = Space::Mountain;
+            }
+        }
+        for x in 0..3 {
+            board[Coordinate { x, y: 0 }] = Space::PlayerEmpty { owner: 0, units: 1 };
+        }
+        board[Coordinate { x: 3, y: 0 }] = Space::Empty;
+
+        assert_eq!(
+            board.border(0).collect::<Vec<_>>(),
+            vec![Coordinate { x: 2, y: 0 }]
+        );
+        assert_eq!(board.owned_by(0).count(), 3);
+    }
+
+    #[test]
+    fn fog_hides_everything_out_of_sight_but_mountains() {
+        let mut board = Board::default();
+        board[Coordinate { x: 0, y: 0 }] = Space::PlayerCapital { owner: 0, units: 5 };
+        board[Coordinate { x: 1, y: 1 }] = Space::NeutralTown {
+            units: 50,
+            growth: 1,
+            garrison: 50,
+        };
+        board[Coordinate { x: 2, y: 2 }] = Space::PlayerEmpty { owner: 1, units: 3 };
+        board[Coordinate { x: 5, y: 5 }] = Space::Mountain;
+
+        let fogged = board.fogged(0);
+        assert_eq!(
+            fogged[Coordinate { x: 1, y: 1 }],
+            board[Coordinate { x: 1, y: 1 }]
+        );
+        assert_eq!(fogged[Coordinate { x: 2, y: 2 }], Space::Empty);
+        assert_eq!(fogged[Coordinate { x: 5, y: 5 }], Space::Mountain);
+        assert!(board.is_visible_to(1, Coordinate { x: 1, y: 1 }));
+        assert!(!board.is_visible_to(1, Coordinate { x: 0, y: 0 }));
+    }
+
+    #[test]
+    fn lookouts_see_further() {
+        let mut board = Board::default();
+        board[Coordinate { x: 0, y: 0 }] = Space::PlayerLookout { owner: 0, units: 1 };
+        board[Coordinate { x: 9, y: 9 }] = Space::PlayerEmpty { owner: 0, units: 1 };
+
+        assert!(board.is_visible_to(0, Coordinate { x: 3, y: 2 }));
+        assert!(!board.is_visible_to(0, Coordinate { x: 4, y: 0 }));
+        assert!(board.is_visible_to(0, Coordinate { x: 10, y: 8 }));
+        assert!(!board.is_visible_to(0, Coordinate { x: 11, y: 9 }));
+    }
+}
@@ -1,15 +1,48 @@
-use std::fmt::Display;
+use std::{error::Error, fmt::Display};
 
 use serde::{Deserialize, Serialize};
 
+pub mod analysis;
+mod board;
+pub mod pathfinding;
+pub mod replay;
+
+pub use board::Board;
+
 pub const BOARD_SIZE: usize = 20;
 
+/// The version of the request and response formats between the game server and bots. Bots tell the
+/// lobby which version they speak when they register, and the lobby turns away any other version.
+///
+/// - Version 2 added the `Swamp`, `PlayerSwamp`, `Lookout` and `PlayerLookout` spaces. It breaks
+///   version 1, since version 1 bots can't read boards with them.
+///
+/// Adding a field to a space isn't a new version, as long as older bots can ignore it and it has a
+/// default for boards that leave it out. Towns gained `growth` (default 1) and neutral towns
+/// gained `garrison` (default 0) this way during version 2.
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// How far a player can see from a lookout they hold, diagonals included
+pub const LOOKOUT_VISION: usize = 3;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, PartialOrd, Ord, Hash)]
 pub struct Coordinate {
     pub x: usize,
     pub y: usize,
 }
 impl Coordinate {
+    pub fn is_in_bounds(&self) -> bool {
+        self.x < BOARD_SIZE && self.y < BOARD_SIZE
+    }
+
+    pub fn is_adjacent_to(&self, other: &Coordinate) -> bool {
+        self.manhattan_distance(other) == 1
+    }
+
+    pub fn manhattan_distance(&self, other: &Coordinate) -> usize {
+        self.x.abs_diff(other.x) + self.y.abs_diff(other.y)
+    }
+
     /**
      * Excludes spaces outside of bounds
      */
@@ -48,58 +81,221 @@ impl Display for Coordinate {
     }
 }
 
+/// Towns from before growth multipliers all grew at the same rate
+fn default_growth() -> usize {
+    1
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum Space {
-    PlayerCapital { owner: usize, units: usize },
-    PlayerTown { owner: usize, units: usize },
-    NeutralTown { units: usize },
-    PlayerEmpty { owner: usize, units: usize },
+    PlayerCapital {
+        owner: usize,
+        units: usize,
+    },
+    PlayerTown {
+        owner: usize,
+        units: usize,
+        /// How many units the town adds each time it grows
+        #[serde(default = "default_growth")]
+        growth: usize,
+    },
+    NeutralTown {
+        units: usize,
+        /// How many units the town will add each time it grows, once someone takes it
+        #[serde(default = "default_growth")]
+        growth: usize,
+        /// The units the town started out with, which it slowly wins back while nobody holds it
+        #[serde(default)]
+        garrison: usize,
+    },
+    PlayerEmpty {
+        owner: usize,
+        units: usize,
+    },
     Empty,
     Mountain,
+    /// Drains a unit from whoever holds it every turn, and goes back to being unowned once empty
+    PlayerSwamp {
+        owner: usize,
+        units: usize,
+    },
+    Swamp,
+    /// Lets whoever holds it see further under fog of war
+    PlayerLookout {
+        owner: usize,
+        units: usize,
+    },
+    Lookout,
 }
 impl Space {
     pub fn get_units(&self) -> usize {
         match self {
             Space::PlayerCapital { owner: _, units } => *units,
-            Space::PlayerTown { owner: _, units } => *units,
-            Space::NeutralTown { units } => *units,
+            Space::PlayerTown { units, .. } => *units,
+            Space::NeutralTown { units, .. } => *units,
             Space::PlayerEmpty { owner: _, units } => *units,
-            Space::Empty | Space::Mountain => 0,
+            Space::PlayerSwamp { owner: _, units } => *units,
+            Space::PlayerLookout { owner: _, units } => *units,
+            Space::Empty | Space::Mountain | Space::Swamp | Space::Lookout => 0,
         }
     }
-    pub fn unsafe_set_units(&mut self, new_units: usize) {
+    pub fn set_units(&mut self, new_units: usize) -> Result<(), SpaceError> {
         match self {
             Space::PlayerCapital { owner: _, units } => *units = new_units,
-            Space::PlayerTown { owner: _, units } => *units = new_units,
-            Space::NeutralTown { units } => *units = new_units,
+            Space::PlayerTown { units, .. } => *units = new_units,
+            Space::NeutralTown { units, .. } => *units = new_units,
             Space::PlayerEmpty { owner: _, units } => *units = new_units,
-            Space::Empty | Space::Mountain => {
-                panic!("Tried to set units on invalid space type")
+            Space::PlayerSwamp { owner: _, units } => *units = new_units,
+            Space::PlayerLookout { owner: _, units } => *units = new_units,
+            Space::Empty | Space::Mountain | Space::Swamp | Space::Lookout => {
+                return Err(SpaceError::CannotHoldUnits(*self))
             }
         }
+        Ok(())
+    }
+    /**
+     * The space after `owner` takes it over with `units` units
+     */
+    pub fn captured_by(&self, owner: usize, units: usize) -> Result<Space, SpaceError> {
+        match self {
+            Space::PlayerCapital { .. } => Ok(Space::PlayerCapital { owner, units }),
+            Space::PlayerTown { growth, .. } | Space::NeutralTown { growth, .. } => {
+                Ok(Space::PlayerTown {
+                    owner,
+                    units,
+                    growth: *growth,
+                })
+            }
+            Space::PlayerEmpty { .. } | Space::Empty => Ok(Space::PlayerEmpty { owner, units }),
+            Space::PlayerSwamp { .. } | Space::Swamp => Ok(Space::PlayerSwamp { owner, units }),
+            Space::PlayerLookout { .. } | Space::Lookout => {
+                Ok(Space::PlayerLookout { owner, units })
+            }
+            Space::Mountain => Err(SpaceError::CannotBeCaptured(*self)),
+        }
+    }
+    pub fn is_passable(&self) -> bool {
+        *self != Space::Mountain
+    }
+    /**
+     * How far the space's owner can see from it under fog of war
+     */
+    pub fn vision(&self) -> usize {
+        match self {
+            Space::PlayerLookout { .. } => LOOKOUT_VISION,
+            _ => 1,
+        }
     }
     pub fn owner(&self) -> Option<usize> {
         match self {
             Space::PlayerCapital { owner, units: _ } => Some(*owner),
-            Space::PlayerTown { owner, units: _ } => Some(*owner),
+            Space::PlayerTown { owner, .. } => Some(*owner),
             Space::PlayerEmpty { owner, units: _ } => Some(*owner),
-            Space::NeutralTown { .. } | Space::Empty | Space::Mountain => None,
+            Space::PlayerSwamp { owner, units: _ } => Some(*owner),
+            Space::PlayerLookout { owner, units: _ } => Some(*owner),
+            Space::NeutralTown { .. }
+            | Space::Empty
+            | Space::Mountain
+            | Space::Swamp
+            | Space::Lookout => None,
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpaceError {
+    CannotHoldUnits(Space),
+    CannotBeCaptured(Space),
+}
+impl Display for SpaceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SpaceError::CannotHoldUnits(space) => write!(f, "{space:?} can't hold units"),
+            SpaceError::CannotBeCaptured(space) => write!(f, "{space:?} can't be captured"),
+        }
+    }
+}
+impl Error for SpaceError {}
+
 pub type Spaces = [[Space; BOARD_SIZE]; BOARD_SIZE];
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TurnRequest {
     pub turn: usize,
     pub player: usize,
-    pub spaces: Spaces,
+    pub spaces: Board,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TurnResponse {
     pub from: Coordinate,
     pub to: Coordinate,
 }
+
+/// Something a bot can do on its turn instead of moving, sent as `{"action": "surrender"}`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum TurnAction {
+    /// Gives up the game. Everything the bot owns turns neutral.
+    Surrender,
+}
+
+/// Everything a bot can answer a turn request with, besides `null` to pass. Bots that only ever
+/// move can keep sending a plain [`TurnResponse`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum TurnReply {
+    Move(TurnResponse),
+    Action(TurnAction),
+}
+
+/// What a bot sends to the game server's lobby to make itself available for games
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BotRegistration {
+    pub name: String,
+    pub author: String,
+    /// Where the server should send turn requests, as `hostname:port`
+    pub endpoint: String,
+    pub protocol_version: u32,
+    /// A CSS color for the bot's spaces. Picked by the server if not given.
+    #[serde(default)]
+    pub color: Option<String>,
+}
+
+/// Colors given to players that didn't pick their own, by seat
+pub const PLAYER_COLORS: [&str; 8] = [
+    "#00ffff", "#ff7e7e", "#99ff5e", "#9c7eff", "#ff5ec4", "#ffb574", "#ecff5e", "#7effbc",
+];
+
+/// Who is playing in a seat of a game
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayerInfo {
+    pub name: String,
+    pub author: String,
+    /// A CSS color, like `#ff7e7e`
+    pub color: String,
+}
+
+impl PlayerInfo {
+    /**
+     * What a player is called when nobody gave it a name
+     */
+    pub fn unnamed(seat: usize) -> Self {
+        PlayerInfo {
+            name: format!("Player {seat}"),
+            author: String::new(),
+            color: PLAYER_COLORS[seat % PLAYER_COLORS.len()].to_string(),
+        }
+    }
+}
+
+/// Sent to every bot's `/start` when a game begins. Bots don't have to handle it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameStart {
+    pub game: String,
+    /// The seat the bot is playing in, which is the `player` of its turn requests
+    pub player: usize,
+    /// Indexed by seat
+    pub players: Vec<PlayerInfo>,
+}
@@ -0,0 +1,360 @@
+//! Shortest paths between spaces. Mountains are never passable, and unreachable targets give
+//! `None` rather than a panic.
+
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, VecDeque},
+};
+
+use crate::{Board, Coordinate, Space, BOARD_SIZE};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path {
+    /// Every space along the path, including the start and the goal
+    pub steps: Vec<Coordinate>,
+    /// The total cost of entering every space after the start
+    pub cost: usize,
+}
+impl Path {
+    /**
+     * The space to move to first, or `None` if the path starts at its goal
+     */
+    pub fn next_step(&self) -> Option<Coordinate> {
+        self.steps.get(1).copied()
+    }
+
+    /**
+     * The number of moves needed to follow the path
+     */
+    pub fn moves(&self) -> usize {
+        self.steps.len() - 1
+    }
+}
+
+/// Distances from one or more origins to every space on the board, found with a breadth-first
+/// search
+#[derive(Debug, Clone)]
+pub struct DistanceMap {
+    origins: Vec<Coordinate>,
+    distances: [[Option<usize>; BOARD_SIZE]; BOARD_SIZE],
+    previous: [[Option<Coordinate>; BOARD_SIZE]; BOARD_SIZE],
+}
+impl DistanceMap {
+    pub fn new(board: &Board, origin: Coordinate) -> Self {
+        Self::from_origins(board, [origin])
+    }
+
+    /**
+     * Distances to the closest of `origins`
+     */
+    pub fn from_origins(board: &Board, origins: impl IntoIterator<Item = Coordinate>) -> Self {
+        let mut map = DistanceMap {
+            origins: origins.into_iter().collect(),
+            distances: [[None; BOARD_SIZE]; BOARD_SIZE],
+            previous: [[None; BOARD_SIZE]; BOARD_SIZE],
+        };
+
+        let mut queue = VecDeque::new();
+        for &origin in &map.origins {
+            if origin.is_in_bounds() && map.distances[origin.x][origin.y].is_none() {
+                map.distances[origin.x][origin.y] = Some(0);
+                queue.push_back((origin, 0));
+            }
+        }
+        while let Some((current, distance)) = queue.pop_front() {
+            for neighbor in board.passable_neighbors(current) {
+                if map.distances[neighbor.x][neighbor.y].is_none() {
+                    map.distances[neighbor.x][neighbor.y] = Some(distance + 1);
+                    map.previous[neighbor.x][neighbor.y] = Some(current);
+                    queue.push_back((neighbor, distance + 1));
+                }
+            }
+        }
+        map
+    }
+
+    /**
+     * The origin the map was built from, or the first of them if there were several. `None` if the
+     * map was built from no origins at all, like the spaces of an eliminated player.
+     */
+    pub fn origin(&self) -> Option<Coordinate> {
+        self.origins.first().copied()
+    }
+
+    pub fn distance(&self, to: Coordinate) -> Option<usize> {
+        if to.is_in_bounds() {
+            self.distances[to.x][to.y]
+        } else {
+            None
+        }
+    }
+
+    pub fn path_to(&self, to: Coordinate) -> Option<Path> {
+        let cost = self.distance(to)?;
+        let mut steps = vec![to];
+        let mut current = to;
+        while let Some(previous) = self.previous[current.x][current.y] {
+            steps.push(previous);
+            current = previous;
+        }
+        steps.reverse();
+        Some(Path { steps, cost })
+    }
+
+    /**
+     * The closest reachable space matching `predicate`, breaking ties by coordinate
+     */
+    pub fn nearest(&self, predicate: impl Fn(Coordinate) -> bool) -> Option<Coordinate> {
+        Board::coordinates()
+            .filter(|c| predicate(*c))
+            .filter_map(|c| Some((self.distance(c)?, c)))
+            .min()
+            .map(|(_, c)| c)
+    }
+}
+
+/// Shortest path using a cost of 1 per move
+pub fn shortest_path(board: &Board, from: Coordinate, to: Coordinate) -> Option<Path> {
+    a_star(board, from, to, |_, _| Some(1))
+}
+
+/// Cheapest path from `from` to `to`, where `cost` gives the price of entering a space, or `None`
+/// if it shouldn't be entered at all. Costs below 1 are treated as 1, which keeps the distance
+/// heuristic from overestimating.
+pub fn a_star<F>(board: &Board, from: Coordinate, to: Coordinate, cost: F) -> Option<Path>
+where
+    F: Fn(Coordinate, Space) -> Option<usize>,
+{
+    if !from.is_in_bounds() || !to.is_in_bounds() {
+        return None;
+    }
+
+    let mut best_costs = [[usize::MAX; BOARD_SIZE]; BOARD_SIZE];
+    let mut previous: [[Option<Coordinate>; BOARD_SIZE]; BOARD_SIZE] =
+        [[None; BOARD_SIZE]; BOARD_SIZE];
+    let mut open_set = BinaryHeap::new();
+
+    best_costs[from.x][from.y] = 0;
+    open_set.push(Reverse((from.manhattan_distance(&to), 0, from)));
+
+    while let Some(Reverse((_, cost_so_far, current))) = open_set.pop() {
+        if current == to {
+            let mut steps = vec![current];
+            let mut current = current;
+            while let Some(parent) = previous[current.x][current.y] {
+                steps.push(parent);
+                current = parent;
+            }
+            steps.reverse();
+            return Some(Path {
+                steps,
+                cost: cost_so_far,
+            });
+        }
+        if cost_so_far > best_costs[current.x][current.y] {
+            // A cheaper way here was already expanded
+            continue;
+        }
+
+        for neighbor in board.passable_neighbors(current) {
+            let Some(step_cost) = cost(neighbor, board[neighbor]) else {
+                continue;
+            };
+            let tentative_cost = cost_so_far + step_cost.max(1);
+            if tentative_cost < best_costs[neighbor.x][neighbor.y] {
+                best_costs[neighbor.x][neighbor.y] = tentative_cost;
+                previous[neighbor.x][neighbor.y] = Some(current);
+                open_set.push(Reverse((
+                    tentative_cost + neighbor.manhattan_distance(&to),
+                    tentative_cost,
+                    neighbor,
+                )));
+            }
+        }
+    }
+    None
+}
+
+/// Move distances between every pair of spaces, for bots that ask about many pairs on a board whose
+/// mountains don't change
+#[derive(Debug, Clone)]
+pub struct AllPairsDistances {
+    /// Indexed by `from`, then `to`, each flattened column by column
+    distances: Vec<Option<usize>>,
+}
+impl AllPairsDistances {
+    pub fn new(board: &Board) -> Self {
+        let mut distances = Vec::with_capacity(BOARD_SIZE.pow(4));
+        for from in Board::coordinates() {
+            let map = DistanceMap::new(board, from);
+            distances.extend(Board::coordinates().map(|to| map.distance(to)));
+        }
+        AllPairsDistances { distances }
+    }
+
+    pub fn distance(&self, from: Coordinate, to: Coordinate) -> Option<usize> {
+        if from.is_in_bounds() && to.is_in_bounds() {
+            self.distances[Self::index(from) * BOARD_SIZE * BOARD_SIZE + Self::index(to)]
+        } else {
+            None
+        }
+    }
+
+    /**
+     * The first move of a shortest path from `from` to `to`
+     */
+    pub fn next_step(&self, from: Coordinate, to: Coordinate) -> Option<Coordinate> {
+        let distance = self.distance(from, to)?.checked_sub(1)?;
+        from.surrounding()
+            .into_iter()
+            .find(|neighbor| self.distance(*neighbor, to) == Some(distance))
+    }
+
+    fn index(c: Coordinate) -> usize {
+        c.x * BOARD_SIZE + c.y
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn c(x: usize, y: usize) -> Coordinate {
+        Coordinate { x, y }
+    }
+
+    /// A board with a wall of mountains along x = 1, open only at y = 5
+    fn walled_board() -> Board {
+        let mut board = Board::default();
+        for y in 0..BOARD_SIZE {
+            if y != 5 {
+                board[c(1, y)] = Space::Mountain;
+            }
+        }
+        board
+    }
+
+    #[test]
+    fn distance_map_goes_around_mountains() {
+        let map = DistanceMap::new(&walled_board(), c(0, 0));
+
+        assert_eq!(map.origin(), Some(c(0, 0)));
+        assert_eq!(map.distance(c(0, 0)), Some(0));
+        assert_eq!(map.distance(c(2, 0)), Some(12));
+        assert_eq!(map.distance(c(1, 0)), None);
+
+        let path = map.path_to(c(2, 0)).unwrap();
+        assert_eq!(path.moves(), 12);
+        assert_eq!(path.steps.first(), Some(&c(0, 0)));
+        assert_eq!(path.steps.last(), Some(&c(2, 0)));
+        assert!(path.steps.contains(&c(1, 5)));
+        assert!(path.steps.windows(2).all(|w| w[0].is_adjacent_to(&w[1])));
+    }
+
+    #[test]
+    fn unreachable_targets_are_none() {
+        let mut board = walled_board();
+        board[c(1, 5)] = Space::Mountain;
+
+        assert_eq!(DistanceMap::new(&board, c(0, 0)).distance(c(2, 0)), None);
+        assert_eq!(DistanceMap::new(&board, c(0, 0)).path_to(c(2, 0)), None);
+        assert_eq!(shortest_path(&board, c(0, 0), c(2, 0)), None);
+        assert_eq!(
+            AllPairsDistances::new(&board).next_step(c(0, 0), c(2, 0)),
+            None
+        );
+    }
+
+    #[test]
+    fn maps_without_origins_reach_nothing() {
+        let board = walled_board();
+        let map = DistanceMap::from_origins(&board, board.owned_by(0));
+
+        assert_eq!(map.origin(), None);
+        assert_eq!(map.distance(c(0, 0)), None);
+        assert_eq!(map.path_to(c(0, 0)), None);
+    }
+
+    #[test]
+    fn nearest_picks_closest_match() {
+        let mut board = Board::default();
+        board[c(5, 5)] = Space::NeutralTown {
+            units: 10,
+            growth: 1,
+            garrison: 10,
+        };
+        board[c(0, 3)] = Space::NeutralTown {
+            units: 10,
+            growth: 1,
+            garrison: 10,
+        };
+
+        let map = DistanceMap::new(&board, c(0, 0));
+        assert_eq!(
+            map.nearest(|c| matches!(board[c], Space::NeutralTown { .. })),
+            Some(c(0, 3))
+        );
+    }
+
+    #[test]
+    fn a_star_matches_breadth_first_search() {
+        let board = walled_board();
+        let map = DistanceMap::new(&board, c(0, 19));
+
+        for to in Board::coordinates() {
+            assert_eq!(
+                shortest_path(&board, c(0, 19), to).map(|path| path.cost),
+                map.distance(to)
+            );
+        }
+    }
+
+    #[test]
+    fn a_star_avoids_expensive_spaces() {
+        let mut board = Board::default();
+        for y in 0..3 {
+            board[c(1, y)] = Space::PlayerEmpty {
+                owner: 1,
+                units: 20,
+            };
+        }
+
+        let path = a_star(&board, c(0, 1), c(2, 1), |_, space| {
+            Some(1 + space.get_units())
+        })
+        .unwrap();
+
+        assert_eq!(path.cost, 6);
+        assert_eq!(path.steps[..3], [c(0, 1), c(0, 2), c(0, 3)]);
+
+        let blocked = a_star(&board, c(0, 1), c(2, 1), |_, space| {
+            (space.get_units() == 0).then_some(1)
+        })
+        .unwrap();
+        assert!(blocked
+            .steps
+            .iter()
+            .all(|step| board[*step].get_units() == 0));
+    }
+
+    #[test]
+    fn all_pairs_agrees_with_distance_maps() {
+        let board = walled_board();
+        let all_pairs = AllPairsDistances::new(&board);
+
+        for from in [c(0, 0), c(2, 7), c(19, 19)] {
+            let map = DistanceMap::new(&board, from);
+            for to in Board::coordinates() {
+                assert_eq!(all_pairs.distance(from, to), map.distance(to));
+            }
+        }
+
+        let mut current = c(0, 0);
+        let mut moves = 0;
+        while current != c(2, 0) {
+            current = all_pairs.next_step(current, c(2, 0)).unwrap();
+            moves += 1;
+        }
+        assert_eq!(moves, 12);
+    }
+}
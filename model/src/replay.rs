@@ -0,0 +1,157 @@
+use std::{error::Error, fmt::Display};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Board, Coordinate, PlayerInfo, Space};
+
+/// A space that changed between two boards
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TileChange {
+    #[serde(flatten)]
+    pub coordinate: Coordinate,
+    pub space: Space,
+}
+
+/**
+ * The spaces of `current` that are different on `previous`
+ */
+pub fn diff(previous: &Board, current: &Board) -> Vec<TileChange> {
+    current
+        .iter()
+        .filter(|(coordinate, space)| previous[*coordinate] != *space)
+        .map(|(coordinate, space)| TileChange { coordinate, space })
+        .collect()
+}
+
+/// Changes come from files and sockets, so they can't be trusted to fit on the board
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayError {
+    OutOfBounds(Coordinate),
+}
+impl Display for ReplayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReplayError::OutOfBounds(coordinate) => {
+                write!(f, "{coordinate} isn't on the board")
+            }
+        }
+    }
+}
+impl Error for ReplayError {}
+
+/**
+ * Makes the changes on `board`, or none of them if any is off the board
+ */
+pub fn apply(board: &mut Board, changes: &[TileChange]) -> Result<(), ReplayError> {
+    if let Some(change) = changes
+        .iter()
+        .find(|change| !change.coordinate.is_in_bounds())
+    {
+        return Err(ReplayError::OutOfBounds(change.coordinate));
+    }
+    for change in changes {
+        board[change.coordinate] = change.space;
+    }
+    Ok(())
+}
+
+/// A recording of a whole game, as the board it started with and what changed each turn
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    pub id: String,
+    /// Indexed by seat
+    pub players: Vec<PlayerInfo>,
+    pub initial: Board,
+    pub turns: Vec<Vec<TileChange>>,
+    pub winner: Option<usize>,
+}
+
+impl Replay {
+    pub fn new(id: String, players: Vec<PlayerInfo>, initial: Board) -> Self {
+        Replay {
+            id,
+            players,
+            initial,
+            turns: vec![],
+            winner: None,
+        }
+    }
+
+    /**
+     * The board at the start, and then after every turn, as long as every turn fits on the board
+     */
+    pub fn boards(&self) -> Result<impl Iterator<Item = Board> + '_, ReplayError> {
+        if let Some(change) = self
+            .turns
+            .iter()
+            .flatten()
+            .find(|change| !change.coordinate.is_in_bounds())
+        {
+            return Err(ReplayError::OutOfBounds(change.coordinate));
+        }
+        Ok(std::iter::once(self.initial).chain(self.turns.iter().scan(
+            self.initial,
+            |board, changes| {
+                apply(board, changes).expect("Every change was checked above");
+                Some(*board)
+            },
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replays_reproduce_every_board() {
+        let initial = Board::default();
+        let mut second = initial;
+        second[Coordinate { x: 1, y: 2 }] = Space::PlayerCapital { owner: 0, units: 5 };
+        let mut third = second;
+        third[Coordinate { x: 1, y: 3 }] = Space::PlayerEmpty { owner: 0, units: 1 };
+        third[Coordinate { x: 1, y: 2 }] = Space::PlayerCapital { owner: 0, units: 4 };
+
+        let mut replay = Replay::new("game".to_string(), vec![PlayerInfo::unnamed(0)], initial);
+        replay.turns.push(diff(&initial, &second));
+        replay.turns.push(diff(&second, &third));
+
+        assert_eq!(replay.turns[0].len(), 1);
+        assert_eq!(replay.turns[1].len(), 2);
+        assert_eq!(
+            replay.boards().unwrap().collect::<Vec<_>>(),
+            vec![initial, second, third]
+        );
+
+        let json = serde_json::to_string(&replay).unwrap();
+        assert_eq!(serde_json::from_str::<Replay>(&json).unwrap(), replay);
+    }
+
+    #[test]
+    fn changes_off_the_board_are_errors() {
+        let mut board = Board::default();
+        let off = Coordinate {
+            x: crate::BOARD_SIZE,
+            y: 0,
+        };
+        let changes = [
+            TileChange {
+                coordinate: Coordinate { x: 0, y: 0 },
+                space: Space::Mountain,
+            },
+            TileChange {
+                coordinate: off,
+                space: Space::Mountain,
+            },
+        ];
+        assert_eq!(
+            apply(&mut board, &changes),
+            Err(ReplayError::OutOfBounds(off))
+        );
+        assert_eq!(board, Board::default());
+
+        let mut replay = Replay::new("game".to_string(), vec![], board);
+        replay.turns.push(changes.to_vec());
+        assert_eq!(replay.boards().err(), Some(ReplayError::OutOfBounds(off)));
+    }
+}
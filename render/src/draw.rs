@@ -0,0 +1,256 @@
+use model::{Board, PlayerInfo, Space, BOARD_SIZE, PLAYER_COLORS};
+
+const GRID: u8 = 0;
+const EMPTY: u8 = 1;
+const MOUNTAIN: u8 = 2;
+const PEAK: u8 = 3;
+const NEUTRAL_TOWN: u8 = 4;
+const MARKER: u8 = 5;
+const SWAMP: u8 = 6;
+/// Player `n` is drawn with color `FIRST_PLAYER + n`
+const FIRST_PLAYER: u8 = 7;
+
+/// Every color a frame can use. Frames are drawn with indexes into it, which both PNG and GIF can
+/// store directly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Palette {
+    colors: Vec<[u8; 3]>,
+}
+
+/// `#rrggbb`
+fn parse_hex(color: &str) -> Option<[u8; 3]> {
+    let hex = color.strip_prefix('#').filter(|hex| hex.len() == 6)?;
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+impl Palette {
+    /// Players whose color isn't `#rrggbb` get the default color for their seat
+    pub fn new(players: &[PlayerInfo]) -> Self {
+        let mut colors = vec![
+            [0x00, 0x00, 0x00],
+            [0xff, 0xff, 0xff],
+            [0x75, 0x75, 0x75],
+            [0xd0, 0xd0, 0xd0],
+            [0xab, 0xab, 0xab],
+            [0x00, 0x00, 0x00],
+            [0x8f, 0xa8, 0x7a],
+        ];
+        colors.extend(
+            players
+                .iter()
+                .enumerate()
+                .take((u8::MAX - FIRST_PLAYER) as usize + 1)
+                .map(|(seat, player)| {
+                    parse_hex(&player.color)
+                        .or_else(|| parse_hex(PLAYER_COLORS[seat % PLAYER_COLORS.len()]))
+                        .unwrap_or([0xff, 0xff, 0xff])
+                }),
+        );
+        Palette { colors }
+    }
+
+    /// The color of the player in `seat`. Seats the replay has no player for wrap around the
+    /// players it does have, or are drawn like empty land if it has none.
+    fn player(&self, seat: usize) -> u8 {
+        let players = self.colors.len() - FIRST_PLAYER as usize;
+        if players == 0 {
+            return EMPTY;
+        }
+        FIRST_PLAYER + (seat % players) as u8
+    }
+
+    /// Red, green and blue of every color, one after the other
+    pub fn rgb(&self) -> Vec<u8> {
+        self.colors.iter().flatten().copied().collect()
+    }
+}
+
+/// One drawn board, as indexes into a [`Palette`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub width: u16,
+    pub height: u16,
+    pub pixels: Vec<u8>,
+}
+
+impl Frame {
+    fn fill(&mut self, left: usize, top: usize, width: usize, height: usize, color: u8) {
+        for y in top..top + height {
+            let row = y * self.width as usize;
+            self.pixels[row + left..row + left + width].fill(color);
+        }
+    }
+}
+
+/// The smallest scale that still leaves room for the markers
+pub const MIN_SCALE: usize = 6;
+
+/// Draws `board` with every space `scale` pixels wide, columns left to right and rows top to
+/// bottom, with a line between spaces. Capitals have a filled square in the middle, towns an
+/// outlined one, mountains a peak, swamps two stripes and lookouts a tower.
+pub fn draw(board: &Board, palette: &Palette, scale: usize) -> Frame {
+    let size = BOARD_SIZE * scale + 1;
+    let mut frame = Frame {
+        width: size as u16,
+        height: size as u16,
+        pixels: vec![GRID; size * size],
+    };
+    let inner = scale - 1;
+    let inset = inner / 4;
+    let marker = inner - 2 * inset;
+    let line = (inner / 8).max(1);
+
+    for c in Board::coordinates() {
+        let left = c.x * scale + 1;
+        let top = c.y * scale + 1;
+        let space = board[c];
+        let background = match space.owner() {
+            Some(owner) => palette.player(owner),
+            None => match space {
+                Space::Mountain => MOUNTAIN,
+                Space::NeutralTown { .. } => NEUTRAL_TOWN,
+                Space::Swamp => SWAMP,
+                _ => EMPTY,
+            },
+        };
+        frame.fill(left, top, inner, inner, background);
+
+        match space {
+            Space::PlayerCapital { .. } => {
+                frame.fill(left + inset, top + inset, marker, marker, MARKER)
+            }
+            Space::PlayerTown { .. } | Space::NeutralTown { .. } => {
+                frame.fill(left + inset, top + inset, marker, marker, MARKER);
+                frame.fill(
+                    left + inset + line,
+                    top + inset + line,
+                    marker - 2 * line,
+                    marker - 2 * line,
+                    background,
+                );
+            }
+            Space::Mountain => {
+                // Widens row by row, to the width of the space at the bottom of the marker
+                for row in 0..marker {
+                    let half = row * inner / marker / 2;
+                    let middle = left + inner / 2;
+                    frame.fill(middle - half, top + inset + row, 2 * half + 1, 1, PEAK);
+                }
+            }
+            Space::PlayerSwamp { .. } | Space::Swamp => {
+                let stripe = if background == SWAMP { MARKER } else { SWAMP };
+                frame.fill(left + inset, top + inset, marker, line, stripe);
+                frame.fill(
+                    left + inset,
+                    top + inner - inset - line,
+                    marker,
+                    line,
+                    stripe,
+                );
+            }
+            Space::PlayerLookout { .. } | Space::Lookout => {
+                let middle = left + inner / 2;
+                frame.fill(middle - line, top + inset, 2 * line + 1, marker, MARKER);
+            }
+            _ => {}
+        }
+    }
+    frame
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use model::Coordinate;
+
+    fn corner(frame: &Frame, scale: usize, coordinate: Coordinate) -> u8 {
+        let (left, top) = (coordinate.x * scale + 1, coordinate.y * scale + 1);
+        frame.pixels[top * frame.width as usize + left]
+    }
+
+    fn center(frame: &Frame, scale: usize, coordinate: Coordinate) -> u8 {
+        let (left, top) = (
+            coordinate.x * scale + scale / 2,
+            coordinate.y * scale + scale / 2,
+        );
+        frame.pixels[top * frame.width as usize + left]
+    }
+
+    #[test]
+    fn spaces_are_told_apart() {
+        let mut board = Board::default();
+        let capital = Coordinate { x: 0, y: 0 };
+        let town = Coordinate { x: 1, y: 0 };
+        let neutral = Coordinate { x: 0, y: 1 };
+        let mountain = Coordinate { x: 1, y: 1 };
+        let swamp = Coordinate { x: 2, y: 0 };
+        let lookout = Coordinate { x: 2, y: 1 };
+        board[capital] = Space::PlayerCapital { owner: 1, units: 5 };
+        board[town] = Space::PlayerTown {
+            owner: 1,
+            units: 5,
+            growth: 1,
+        };
+        board[neutral] = Space::NeutralTown {
+            units: 50,
+            growth: 1,
+            garrison: 50,
+        };
+        board[mountain] = Space::Mountain;
+        board[swamp] = Space::Swamp;
+        board[lookout] = Space::PlayerLookout { owner: 0, units: 1 };
+
+        let scale = 16;
+        let players = [PlayerInfo::unnamed(0), PlayerInfo::unnamed(1)];
+        let frame = draw(&board, &Palette::new(&players), scale);
+        assert_eq!(frame.width as usize, BOARD_SIZE * scale + 1);
+        assert_eq!(
+            frame.pixels.len(),
+            frame.width as usize * frame.height as usize
+        );
+
+        assert_eq!(corner(&frame, scale, capital), FIRST_PLAYER + 1);
+        assert_eq!(center(&frame, scale, capital), MARKER);
+        assert_eq!(corner(&frame, scale, town), FIRST_PLAYER + 1);
+        assert_eq!(center(&frame, scale, town), FIRST_PLAYER + 1);
+        assert_eq!(corner(&frame, scale, neutral), NEUTRAL_TOWN);
+        assert_eq!(corner(&frame, scale, mountain), MOUNTAIN);
+        assert_eq!(center(&frame, scale, mountain), PEAK);
+        assert_eq!(corner(&frame, scale, swamp), SWAMP);
+        assert_eq!(corner(&frame, scale, lookout), FIRST_PLAYER);
+        assert_eq!(center(&frame, scale, lookout), MARKER);
+        assert_eq!(frame.pixels[0], GRID);
+    }
+
+    #[test]
+    fn owners_without_a_player_stay_in_the_palette() {
+        let mut board = Board::default();
+        let stray = Coordinate { x: 0, y: 0 };
+        board[stray] = Space::PlayerEmpty {
+            owner: 300,
+            units: 1,
+        };
+
+        let scale = MIN_SCALE;
+        let frame = draw(&board, &Palette::new(&[PlayerInfo::unnamed(0)]), scale);
+        assert_eq!(corner(&frame, scale, stray), FIRST_PLAYER);
+        let frame = draw(&board, &Palette::new(&[]), scale);
+        assert_eq!(corner(&frame, scale, stray), EMPTY);
+    }
+
+    #[test]
+    fn palette_falls_back_to_seat_colors() {
+        let players = [
+            PlayerInfo::unnamed(0),
+            PlayerInfo {
+                color: "red".to_string(),
+                ..PlayerInfo::unnamed(1)
+            },
+        ];
+        let rgb = Palette::new(&players).rgb();
+        let first = FIRST_PLAYER as usize * 3;
+        assert_eq!(rgb[first..first + 3], [0x00, 0xff, 0xff]);
+        assert_eq!(rgb[first + 3..first + 6], [0xff, 0x7e, 0x7e]);
+    }
+}
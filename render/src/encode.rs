@@ -0,0 +1,70 @@
+use std::io::Write;
+
+use crate::draw::{Frame, Palette};
+
+fn png_encoder<W: Write>(
+    out: W,
+    palette: &Palette,
+    width: u16,
+    height: u16,
+) -> png::Encoder<'static, W> {
+    let mut encoder = png::Encoder::new(out, width.into(), height.into());
+    encoder.set_color(png::ColorType::Indexed);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_palette(palette.rgb());
+    encoder
+}
+
+pub fn write_png(
+    out: impl Write,
+    palette: &Palette,
+    frame: &Frame,
+) -> Result<(), png::EncodingError> {
+    let mut writer = png_encoder(out, palette, frame.width, frame.height).write_header()?;
+    writer.write_image_data(&frame.pixels)?;
+    writer.finish()
+}
+
+/// An animated PNG that loops forever. `count` has to be the number of `frames`, since it's
+/// written before them.
+pub fn write_apng(
+    out: impl Write,
+    palette: &Palette,
+    count: usize,
+    mut frames: impl Iterator<Item = Frame>,
+    frame_ms: u16,
+) -> Result<(), png::EncodingError> {
+    let Some(first) = frames.next() else {
+        return Ok(());
+    };
+    let mut encoder = png_encoder(out, palette, first.width, first.height);
+    encoder.set_animated(count as u32, 0)?;
+    encoder.set_frame_delay(frame_ms, 1000)?;
+    let mut writer = encoder.write_header()?;
+    for frame in std::iter::once(first).chain(frames) {
+        writer.write_image_data(&frame.pixels)?;
+    }
+    writer.finish()
+}
+
+/// A GIF that loops forever. GIFs count time in hundredths of a second, so `frame_ms` is rounded to
+/// the nearest 10ms.
+pub fn write_gif(
+    out: impl Write,
+    palette: &Palette,
+    mut frames: impl Iterator<Item = Frame>,
+    frame_ms: u16,
+) -> Result<(), gif::EncodingError> {
+    let Some(first) = frames.next() else {
+        return Ok(());
+    };
+    let mut encoder = gif::Encoder::new(out, first.width, first.height, &palette.rgb())?;
+    encoder.set_repeat(gif::Repeat::Infinite)?;
+    for frame in std::iter::once(first).chain(frames) {
+        let mut gif_frame =
+            gif::Frame::from_indexed_pixels(frame.width, frame.height, frame.pixels, None);
+        gif_frame.delay = (frame_ms + 5) / 10;
+        encoder.write_frame(&gif_frame)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,145 @@
+//! Draws a saved replay as an image, to share a game without a server. Run with
+//! `cargo run -p render -- <replay.json> <output.png|output.gif> [--turn N] [--animate]
+//! [--frame-ms N] [--scale N]`.
+//!
+//! A `.gif` is animated over the whole game, and a `.png` is a still of the end of the game unless
+//! `--animate` is given. `--turn` picks a single turn for either (0 is the starting board).
+
+use std::{fs::File, io::BufWriter, path::PathBuf, process};
+
+use draw::{draw, Palette, MIN_SCALE};
+use model::replay::Replay;
+
+mod draw;
+mod encode;
+
+struct Args {
+    replay: PathBuf,
+    output: PathBuf,
+    turn: Option<usize>,
+    animate: bool,
+    frame_ms: u16,
+    scale: usize,
+}
+
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut paths = vec![];
+        let mut turn = None;
+        let mut animate = false;
+        let mut frame_ms = 100;
+        let mut scale = 16;
+
+        while let Some(arg) = args.next() {
+            let mut number = |name: &str| -> Result<usize, String> {
+                let value = args.next().ok_or_else(|| format!("{name} needs a value"))?;
+                value
+                    .parse()
+                    .map_err(|_| format!("{name} should be a number, got '{value}'"))
+            };
+            match arg.as_str() {
+                "--turn" => turn = Some(number("--turn")?),
+                "--frame-ms" => {
+                    frame_ms = number("--frame-ms")?
+                        .try_into()
+                        .map_err(|_| "--frame-ms is too long".to_string())?
+                }
+                "--scale" => scale = number("--scale")?,
+                "--animate" => animate = true,
+                _ if arg.starts_with("--") => return Err(format!("Unknown option '{arg}'")),
+                _ => paths.push(PathBuf::from(arg)),
+            }
+        }
+
+        let [replay, output] = <[PathBuf; 2]>::try_from(paths).map_err(|_| {
+            "Expected a replay file and an output file, like 'replays/abc.json game.gif'"
+                .to_string()
+        })?;
+        if scale < MIN_SCALE {
+            return Err(format!("--scale should be at least {MIN_SCALE}"));
+        }
+        // Leave room for the grid lines in a u16 image size
+        if scale * model::BOARD_SIZE >= u16::MAX as usize {
+            return Err("--scale is too big".to_string());
+        }
+        Ok(Args {
+            replay,
+            output,
+            turn,
+            animate,
+            frame_ms,
+            scale,
+        })
+    }
+}
+
+enum Format {
+    Gif,
+    AnimatedPng,
+    Png,
+}
+
+fn run(args: Args) -> Result<(), String> {
+    let format = match args.output.extension().and_then(|ext| ext.to_str()) {
+        Some("gif") => Format::Gif,
+        Some("png") if args.animate => Format::AnimatedPng,
+        Some("png") => Format::Png,
+        _ => {
+            return Err(format!(
+                "Don't know how to write {}, expected a .png or .gif",
+                args.output.display()
+            ))
+        }
+    };
+    let replay: Replay = File::open(&args.replay)
+        .map_err(|err| err.to_string())
+        .and_then(|file| serde_json::from_reader(file).map_err(|err| err.to_string()))
+        .map_err(|err| format!("Couldn't read {}: {err}", args.replay.display()))?;
+    let turns = replay.turns.len();
+    if args.turn.is_some_and(|turn| turn > turns) {
+        return Err(format!("The game only lasted {turns} turns"));
+    }
+
+    // Skipping boards still applies every turn before them, but only the ones kept are drawn
+    let (skip, count) = match (args.turn, &format) {
+        (Some(turn), _) => (turn, 1),
+        (None, Format::Png) => (turns, 1),
+        (None, _) => (0, turns + 1),
+    };
+    let palette = Palette::new(&replay.players);
+    let mut frames = replay
+        .boards()
+        .map_err(|err| format!("Couldn't read {}: {err}", args.replay.display()))?
+        .skip(skip)
+        .map(|board| draw(&board, &palette, args.scale));
+
+    let out = BufWriter::new(
+        File::create(&args.output)
+            .map_err(|err| format!("Couldn't create {}: {err}", args.output.display()))?,
+    );
+    let result: Result<(), Box<dyn std::error::Error>> = match format {
+        Format::Gif => {
+            encode::write_gif(out, &palette, frames.take(count), args.frame_ms).map_err(Into::into)
+        }
+        Format::AnimatedPng => {
+            encode::write_apng(out, &palette, count, frames.take(count), args.frame_ms)
+                .map_err(Into::into)
+        }
+        Format::Png => {
+            let frame = frames.next().expect("There's always a first board");
+            encode::write_png(out, &palette, &frame).map_err(Into::into)
+        }
+    };
+    result.map_err(|err| format!("Couldn't write {}: {err}", args.output.display()))
+}
+
+fn main() {
+    let args = Args::parse(std::env::args().skip(1)).unwrap_or_else(|err| {
+        eprintln!("Error: {}", err);
+        process::exit(1);
+    });
+    run(args).unwrap_or_else(|err| {
+        eprintln!("Error: {}", err);
+        process::exit(1);
+    });
+}
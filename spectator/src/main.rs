@@ -0,0 +1,107 @@
+//! Watches a game in the terminal, for when there's no browser around. Run with
+//! `cargo run -p spectator -- <server host:port> [game id]`; without a game id, it watches the
+//! newest game on the server.
+
+use std::{io, process};
+
+use crossterm::{
+    cursor::{Hide, Show},
+    execute,
+    terminal::{EnterAlternateScreen, LeaveAlternateScreen},
+};
+use futures_util::StreamExt;
+use protocol::{Message, Watched};
+use serde::Deserialize;
+use tokio_tungstenite::{connect_async, tungstenite};
+
+mod protocol;
+mod render;
+
+#[derive(Deserialize)]
+struct GameListing {
+    id: String,
+}
+
+async fn newest_game(server: &str) -> Result<String, String> {
+    let games: Vec<GameListing> = reqwest::get(format!("http://{server}/games"))
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|err| format!("couldn't list games on {server}: {err}"))?
+        .json()
+        .await
+        .map_err(|err| format!("couldn't list games on {server}: {err}"))?;
+    games
+        .into_iter()
+        .last()
+        .map(|game| game.id)
+        .ok_or_else(|| format!("{server} isn't hosting any games yet"))
+}
+
+/// Streams the game to the screen until the server closes the socket or the user hits Ctrl-C.
+/// Returns the game as it was last seen.
+async fn watch(server: &str, game_id: &str) -> Result<Watched, String> {
+    let (mut socket, _) = connect_async(format!("ws://{server}/spectate/{game_id}"))
+        .await
+        .map_err(|err| format!("couldn't connect to game {game_id} on {server}: {err}"))?;
+
+    let mut watched = Watched::default();
+    let mut stdout = io::stdout();
+    loop {
+        let message = tokio::select! {
+            message = socket.next() => message,
+            _ = tokio::signal::ctrl_c() => break,
+        };
+        match message {
+            Some(Ok(tungstenite::Message::Text(text))) => {
+                let message: Message = serde_json::from_str(&text)
+                    .map_err(|err| format!("couldn't read spectator message: {err}"))?;
+                watched
+                    .update(message)
+                    .map_err(|err| format!("couldn't follow the game: {err}"))?;
+                render::render(&mut stdout, game_id, &watched)
+                    .map_err(|err| format!("couldn't draw the game: {err}"))?;
+            }
+            Some(Ok(tungstenite::Message::Close(_))) | None => break,
+            Some(Ok(_)) => {}
+            Some(Err(err)) => return Err(format!("lost connection to {server}: {err}")),
+        }
+    }
+    Ok(watched)
+}
+
+#[tokio::main]
+async fn main() {
+    let mut args = std::env::args().skip(1);
+    let Some(server) = args.next() else {
+        eprintln!("Usage: spectator <server host:port> [game id]");
+        process::exit(1);
+    };
+    let server = server
+        .trim_start_matches("http://")
+        .trim_end_matches('/')
+        .to_string();
+    let game_id = match args.next() {
+        Some(id) => id,
+        None => newest_game(&server).await.unwrap_or_else(|err| {
+            eprintln!("Error: {}", err);
+            process::exit(1);
+        }),
+    };
+
+    let mut stdout = io::stdout();
+    let _ = execute!(stdout, EnterAlternateScreen, Hide);
+    let result = watch(&server, &game_id).await;
+    let _ = execute!(stdout, Show, LeaveAlternateScreen);
+
+    match result {
+        // Leave the final board on the normal screen, so it's still there after exiting
+        Ok(watched) if !watched.players.is_empty() => {
+            let _ = render::render(&mut stdout, &game_id, &watched);
+        }
+        Ok(_) => {}
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use model::{
+    replay::{apply, ReplayError, TileChange},
+    Board, PlayerInfo,
+};
+use serde::Deserialize;
+
+/// Where one player stood at the end of a turn
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub struct PlayerStats {
+    pub land: usize,
+    pub units: usize,
+    pub towns: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub struct Control {
+    pub paused: bool,
+    pub aborted: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Summary {
+    pub winner: Option<usize>,
+    pub turns: usize,
+    pub aborted: bool,
+}
+
+/// The parts of the server's spectator messages this client uses. Everything else is ignored.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Message {
+    Snapshot {
+        spaces: Box<Board>,
+        turn: usize,
+        players: Vec<PlayerInfo>,
+        control: Control,
+        history: Vec<Vec<PlayerStats>>,
+        summary: Option<Summary>,
+    },
+    Diff {
+        turn: usize,
+        changes: Vec<TileChange>,
+        players: Vec<PlayerInfo>,
+        control: Control,
+        stats: Vec<PlayerStats>,
+        summary: Option<Summary>,
+    },
+}
+
+/// A game as seen from the spectator socket, kept up to date from the snapshot and the diffs after
+/// it
+#[derive(Debug, Clone, Default)]
+pub struct Watched {
+    pub board: Board,
+    pub turn: usize,
+    pub players: Vec<PlayerInfo>,
+    pub control: Control,
+    /// Indexed by player
+    pub stats: Vec<PlayerStats>,
+    pub summary: Option<Summary>,
+}
+
+impl Watched {
+    pub fn update(&mut self, message: Message) -> Result<(), ReplayError> {
+        match message {
+            Message::Snapshot {
+                spaces,
+                turn,
+                players,
+                control,
+                mut history,
+                summary,
+            } => {
+                self.board = *spaces;
+                self.turn = turn;
+                self.players = players;
+                self.control = control;
+                self.stats = history.pop().unwrap_or_default();
+                self.summary = summary;
+            }
+            Message::Diff {
+                turn,
+                changes,
+                players,
+                control,
+                stats,
+                summary,
+            } => {
+                apply(&mut self.board, &changes)?;
+                self.turn = turn;
+                self.players = players;
+                self.control = control;
+                self.stats = stats;
+                self.summary = summary;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use model::{Coordinate, Space};
+    use serde_json::json;
+
+    #[test]
+    fn diffs_update_the_snapshot() {
+        let mut watched = Watched::default();
+        let players = json!([{ "name": "rando", "author": "", "color": "#00ffff" }]);
+        let snapshot = json!({
+            "type": "snapshot",
+            "spaces": Board::default(),
+            "turn": 3,
+            "players": players,
+            "latency": [],
+            "control": { "paused": false, "pending_steps": 0, "tick_ms": 50, "aborted": false },
+            "history": [[{ "land": 1, "units": 5, "towns": 1, "rejected": 0 }]],
+            "summary": null,
+        });
+        watched
+            .update(serde_json::from_value(snapshot).unwrap())
+            .unwrap();
+        assert_eq!(watched.turn, 3);
+        assert_eq!(watched.stats[0].units, 5);
+
+        let diff = json!({
+            "type": "diff",
+            "turn": 4,
+            "changes": [{ "x": 1, "y": 2, "space": { "type": "PlayerEmpty", "owner": 0, "units": 2 } }],
+            "players": players,
+            "control": { "paused": true, "pending_steps": 0, "tick_ms": 50, "aborted": false },
+            "stats": [{ "land": 2, "units": 6, "towns": 1, "rejected": 0 }],
+            "summary": { "winner": 0, "turns": 4, "aborted": false },
+        });
+        watched
+            .update(serde_json::from_value(diff).unwrap())
+            .unwrap();
+        assert_eq!(watched.turn, 4);
+        assert!(watched.control.paused);
+        assert_eq!(
+            watched.board[Coordinate { x: 1, y: 2 }],
+            Space::PlayerEmpty { owner: 0, units: 2 }
+        );
+        assert_eq!(watched.stats[0].land, 2);
+        assert_eq!(watched.summary.unwrap().winner, Some(0));
+    }
+}
@@ -0,0 +1,214 @@
+use std::io::{self, Write};
+
+use crossterm::{
+    cursor::MoveTo,
+    queue,
+    style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor},
+    terminal::{Clear, ClearType},
+};
+use model::{Coordinate, PlayerInfo, Space, BOARD_SIZE, PLAYER_COLORS};
+
+use crate::protocol::Watched;
+
+const NEUTRAL_TOWN: Color = Color::Rgb {
+    r: 0xab,
+    g: 0xab,
+    b: 0xab,
+};
+const SWAMP: Color = Color::Rgb {
+    r: 0x8f,
+    g: 0xa8,
+    b: 0x7a,
+};
+const MOUNTAIN: Color = Color::Rgb {
+    r: 0x75,
+    g: 0x75,
+    b: 0x75,
+};
+
+/// `#rrggbb`, the only kind of CSS color a terminal can show
+fn parse_hex(color: &str) -> Option<Color> {
+    let hex = color.strip_prefix('#').filter(|hex| hex.len() == 6)?;
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some(Color::Rgb {
+        r: channel(0)?,
+        g: channel(2)?,
+        b: channel(4)?,
+    })
+}
+
+/// The player's own color, or the default for their seat if it isn't one a terminal can show
+fn player_color(players: &[PlayerInfo], seat: usize) -> Color {
+    players
+        .get(seat)
+        .and_then(|player| parse_hex(&player.color))
+        .or_else(|| parse_hex(PLAYER_COLORS[seat % PLAYER_COLORS.len()]))
+        .unwrap_or(Color::White)
+}
+
+/// At most 3 characters, so every space fits in 4 columns
+fn short_units(units: usize) -> String {
+    if units < 1000 {
+        units.to_string()
+    } else {
+        format!("{}k", (units / 1000).min(99))
+    }
+}
+
+/// `T` for towns that grow faster than usual
+fn town_marker(growth: usize) -> char {
+    if growth > 1 {
+        'T'
+    } else {
+        't'
+    }
+}
+
+/// The text and background of one space
+fn space_cell(space: Space, players: &[PlayerInfo]) -> (String, Option<Color>) {
+    match space {
+        Space::PlayerCapital { owner, units } => (
+            format!("*{:>3}", short_units(units)),
+            Some(player_color(players, owner)),
+        ),
+        Space::PlayerTown {
+            owner,
+            units,
+            growth,
+        } => (
+            format!("{}{:>3}", town_marker(growth), short_units(units)),
+            Some(player_color(players, owner)),
+        ),
+        Space::PlayerEmpty { owner, units } => (
+            format!("{:>4}", short_units(units)),
+            Some(player_color(players, owner)),
+        ),
+        Space::PlayerSwamp { owner, units } => (
+            format!("~{:>3}", short_units(units)),
+            Some(player_color(players, owner)),
+        ),
+        Space::PlayerLookout { owner, units } => (
+            format!("o{:>3}", short_units(units)),
+            Some(player_color(players, owner)),
+        ),
+        Space::NeutralTown { units, growth, .. } => (
+            format!("{}{:>3}", town_marker(growth), short_units(units)),
+            Some(NEUTRAL_TOWN),
+        ),
+        Space::Empty => ("   .".to_string(), None),
+        Space::Mountain => ("^^^^".to_string(), Some(MOUNTAIN)),
+        Space::Swamp => ("~~~~".to_string(), Some(SWAMP)),
+        Space::Lookout => ("   o".to_string(), None),
+    }
+}
+
+/// Redraws the whole screen: the turn counter, the board (one row per `y`), and a leaderboard
+pub fn render(out: &mut impl Write, game_id: &str, watched: &Watched) -> io::Result<()> {
+    queue!(out, MoveTo(0, 0), Clear(ClearType::All))?;
+
+    let status = match &watched.summary {
+        Some(_) => "Game over",
+        None if watched.control.paused => "Paused",
+        None => "Running",
+    };
+    queue!(
+        out,
+        Print(format!(
+            "Game {game_id}  Turn {}  {status}\r\n\r\n",
+            watched.turn
+        ))
+    )?;
+
+    for y in 0..BOARD_SIZE {
+        for x in 0..BOARD_SIZE {
+            let (text, background) =
+                space_cell(watched.board[Coordinate { x, y }], &watched.players);
+            match background {
+                Some(background) => queue!(
+                    out,
+                    SetBackgroundColor(background),
+                    SetForegroundColor(Color::Black),
+                    Print(text),
+                    ResetColor
+                )?,
+                None => queue!(out, Print(text))?,
+            }
+        }
+        queue!(out, Print("\r\n"))?;
+    }
+    queue!(out, Print("\r\n"))?;
+
+    for (seat, player) in watched.players.iter().enumerate() {
+        let stats = watched.stats.get(seat).copied().unwrap_or_default();
+        let author = if player.author.is_empty() {
+            String::new()
+        } else {
+            format!(" by {}", player.author)
+        };
+        queue!(
+            out,
+            SetBackgroundColor(player_color(&watched.players, seat)),
+            Print("  "),
+            ResetColor,
+            Print(format!(
+                " {:<24} Land: {:>4}  Units: {:>6}  Towns: {:>2}{}\r\n",
+                format!("{}{author}", player.name),
+                stats.land,
+                stats.units,
+                stats.towns,
+                if stats.land == 0 { "  (out)" } else { "" },
+            ))
+        )?;
+    }
+
+    if let Some(summary) = &watched.summary {
+        let result = match summary.winner {
+            _ if summary.aborted => format!("Aborted after {} turns", summary.turns),
+            Some(winner) => format!(
+                "{} won after {} turns",
+                watched
+                    .players
+                    .get(winner)
+                    .map_or("Nobody", |player| player.name.as_str()),
+                summary.turns
+            ),
+            None => format!("No winner after {} turns", summary.turns),
+        };
+        queue!(out, Print(format!("\r\n{result}\r\n")))?;
+    }
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_hex_colors_are_used() {
+        let players = vec![
+            PlayerInfo {
+                name: "a".to_string(),
+                author: String::new(),
+                color: "#102030".to_string(),
+            },
+            PlayerInfo {
+                name: "b".to_string(),
+                author: String::new(),
+                color: "rebeccapurple".to_string(),
+            },
+        ];
+        assert_eq!(
+            player_color(&players, 0),
+            Color::Rgb {
+                r: 0x10,
+                g: 0x20,
+                b: 0x30
+            }
+        );
+        assert_eq!(
+            player_color(&players, 1),
+            parse_hex(PLAYER_COLORS[1]).unwrap()
+        );
+        assert_eq!(short_units(12345), "12k");
+    }
+}
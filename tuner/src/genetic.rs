@@ -0,0 +1,112 @@
+use rand::prelude::*;
+
+pub struct GeneticConfig {
+    pub population: usize,
+    pub generations: usize,
+    /// How many of the best candidates survive each generation unchanged
+    pub elites: usize,
+    /// Standard deviation of mutations, relative to the size of each parameter
+    pub mutation_scale: f64,
+}
+
+/// Evolves parameter vectors starting around `initial`, and returns the best one found along with
+/// its fitness. `fitness` is called once per candidate per generation, and gets every candidate of
+/// a generation at once so it can evaluate them in parallel. `on_generation` is told about the best
+/// candidate after every generation.
+pub fn optimize(
+    initial: &[f64],
+    config: &GeneticConfig,
+    fitness: impl Fn(&[Vec<f64>]) -> Vec<f64>,
+    mut on_generation: impl FnMut(usize, &[f64], f64),
+) -> (Vec<f64>, f64) {
+    let mut rng = thread_rng();
+    let mut population: Vec<Vec<f64>> = std::iter::once(initial.to_vec())
+        .chain((1..config.population).map(|_| mutate(initial, config.mutation_scale, &mut rng)))
+        .collect();
+    let mut best = (initial.to_vec(), f64::NEG_INFINITY);
+
+    for generation in 0..config.generations {
+        let scores = fitness(&population);
+        let mut ranked: Vec<(Vec<f64>, f64)> = population.into_iter().zip(scores).collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        if ranked[0].1 > best.1 {
+            best = ranked[0].clone();
+        }
+        on_generation(generation, &ranked[0].0, ranked[0].1);
+
+        population = ranked
+            .iter()
+            .take(config.elites)
+            .map(|(params, _)| params.clone())
+            .collect();
+        while population.len() < config.population {
+            let mother = tournament(&ranked, &mut rng);
+            let father = tournament(&ranked, &mut rng);
+            let child: Vec<f64> = mother
+                .iter()
+                .zip(father)
+                .map(|(m, f)| if rng.gen_bool(0.5) { *m } else { *f })
+                .collect();
+            population.push(mutate(&child, config.mutation_scale, &mut rng));
+        }
+    }
+
+    best
+}
+
+/// Picks the fittest of three random candidates
+fn tournament<'a>(ranked: &'a [(Vec<f64>, f64)], rng: &mut ThreadRng) -> &'a [f64] {
+    &ranked
+        .choose_multiple(rng, 3)
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .expect("Population is never empty")
+        .0
+}
+
+fn mutate(params: &[f64], scale: f64, rng: &mut ThreadRng) -> Vec<f64> {
+    params
+        .iter()
+        .map(|p| p + gaussian(rng) * scale * p.abs().max(1.0))
+        .collect()
+}
+
+/// A standard normal sample, using the Box-Muller transform
+fn gaussian(rng: &mut ThreadRng) -> f64 {
+    let u1: f64 = rng.gen_range(f64::EPSILON..1.0);
+    let u2: f64 = rng.gen();
+    (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_maximum_of_simple_function() {
+        let target = [3.0, -2.0];
+        let (best, score) = optimize(
+            &[0.0, 0.0],
+            &GeneticConfig {
+                population: 30,
+                generations: 60,
+                elites: 2,
+                mutation_scale: 0.3,
+            },
+            |population| {
+                population
+                    .iter()
+                    .map(|p| {
+                        -p.iter()
+                            .zip(target)
+                            .map(|(a, b)| (a - b).powi(2))
+                            .sum::<f64>()
+                    })
+                    .collect()
+            },
+            |_, _, _| {},
+        );
+
+        assert!(score > -0.1, "best was {best:?} with score {score}");
+    }
+}
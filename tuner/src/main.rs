@@ -0,0 +1,171 @@
+use std::{process, thread};
+
+use genetic::{optimize, GeneticConfig};
+use jroylance_closest_conquerable_space::DistanceCache;
+use self_play::{play_game, Player};
+
+mod genetic;
+mod self_play;
+
+/// A heuristic bot whose weights can be tuned
+#[derive(Clone, Copy)]
+enum Tunable {
+    AimForCities,
+    ClosestConquerableSpace,
+}
+
+impl Tunable {
+    fn from_arg(arg: &str) -> Result<Self, String> {
+        match arg {
+            "bhintze-aim-for-cities" => Ok(Tunable::AimForCities),
+            "jroylance-closest-conquerable-space" => Ok(Tunable::ClosestConquerableSpace),
+            _ => Err(format!(
+                "Unknown bot '{arg}'. Expected 'bhintze-aim-for-cities' or 'jroylance-closest-conquerable-space'."
+            )),
+        }
+    }
+
+    fn default_params(self) -> Vec<f64> {
+        match self {
+            Tunable::AimForCities => bhintze_aim_for_cities_ai::Params::default().to_vec(),
+            Tunable::ClosestConquerableSpace => {
+                jroylance_closest_conquerable_space::Params::default().to_vec()
+            }
+        }
+    }
+
+    fn player(self, params: &[f64]) -> Player {
+        match self {
+            Tunable::AimForCities => {
+                let params = bhintze_aim_for_cities_ai::Params::from_slice(params)
+                    .expect("Params come from default_params");
+                Box::new(move |request| bhintze_aim_for_cities_ai::choose_move(request, &params))
+            }
+            Tunable::ClosestConquerableSpace => {
+                let params = jroylance_closest_conquerable_space::Params::from_slice(params)
+                    .expect("Params come from default_params");
+                let mut cache = DistanceCache::new();
+                Box::new(move |request| {
+                    jroylance_closest_conquerable_space::choose_move(request, &params, &mut cache)
+                })
+            }
+        }
+    }
+
+    /// The params file that the bot's `--params` flag loads
+    fn params_json(self, params: &[f64]) -> String {
+        match self {
+            Tunable::AimForCities => {
+                serde_json::to_string_pretty(&bhintze_aim_for_cities_ai::Params::from_slice(params))
+            }
+            Tunable::ClosestConquerableSpace => serde_json::to_string_pretty(
+                &jroylance_closest_conquerable_space::Params::from_slice(params),
+            ),
+        }
+        .expect("Params always serialize")
+    }
+}
+
+struct Options {
+    bot: Tunable,
+    genetic: GeneticConfig,
+    games: usize,
+    max_turns: usize,
+    output: String,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let usage = "Usage: tuner <BOT> [--generations N] [--population N] [--games N] [--max-turns N] [--output FILE]";
+    let mut args = std::env::args().skip(1);
+    let bot = Tunable::from_arg(&args.next().ok_or(usage)?)?;
+    let mut options = Options {
+        bot,
+        genetic: GeneticConfig {
+            population: 12,
+            generations: 10,
+            elites: 2,
+            mutation_scale: 0.2,
+        },
+        games: 4,
+        max_turns: 300,
+        output: "params.json".to_string(),
+    };
+
+    while let Some(flag) = args.next() {
+        let value = args.next().ok_or(usage)?;
+        let number = || {
+            value
+                .parse::<usize>()
+                .map_err(|_| format!("Expected a number after {flag}, got '{value}'"))
+        };
+        match flag.as_str() {
+            "--generations" => options.genetic.generations = number()?,
+            "--population" => options.genetic.population = number()?.max(options.genetic.elites),
+            "--games" => options.games = number()?,
+            "--max-turns" => options.max_turns = number()?,
+            "--output" => options.output = value,
+            _ => return Err(usage.to_string()),
+        }
+    }
+    Ok(options)
+}
+
+/// Average score of `params` over games against the bot's default params, alternating who moves
+/// first
+fn fitness(options: &Options, params: &[f64]) -> f64 {
+    let baseline = options.bot.default_params();
+    let total: f64 = (0..options.games)
+        .map(|game| {
+            let seat = game % 2;
+            let mut players = if seat == 0 {
+                [options.bot.player(params), options.bot.player(&baseline)]
+            } else {
+                [options.bot.player(&baseline), options.bot.player(params)]
+            };
+            play_game(&mut players, options.max_turns)
+                .map(|outcome| outcome.score(seat))
+                .unwrap_or(0.0)
+        })
+        .sum();
+    total / options.games.max(1) as f64
+}
+
+fn main() {
+    let options = parse_args().unwrap_or_else(|err| {
+        eprintln!("Error: {}", err);
+        process::exit(1);
+    });
+
+    let (best, score) = optimize(
+        &options.bot.default_params(),
+        &options.genetic,
+        |population| {
+            thread::scope(|scope| {
+                let handles: Vec<_> = population
+                    .iter()
+                    .map(|params| scope.spawn(|| fitness(&options, params)))
+                    .collect();
+                handles
+                    .into_iter()
+                    .map(|handle| handle.join().unwrap_or(0.0))
+                    .collect()
+            })
+        },
+        |generation, best, score| {
+            println!("Generation {generation}: best score {score:.3} with {best:?}");
+            // Save progress as we go, so stopping early still leaves something usable
+            if let Err(err) = std::fs::write(&options.output, options.bot.params_json(best)) {
+                eprintln!("Couldn't write {}: {err}", options.output);
+            }
+        },
+    );
+
+    if let Err(err) = std::fs::write(&options.output, options.bot.params_json(&best)) {
+        eprintln!("Couldn't write {}: {err}", options.output);
+        process::exit(1);
+    }
+    println!(
+        "Best score {score:.3}, written to {}. Load it with `--params {}`.",
+        options.output, options.output
+    );
+}
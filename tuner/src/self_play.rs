@@ -0,0 +1,61 @@
+use engine::{GameState, GenerationError, TownConfig};
+use model::{analysis::player_totals, TurnRequest, TurnResponse};
+
+/// A bot that can be asked for moves without going through HTTP
+pub type Player = Box<dyn FnMut(&TurnRequest) -> Option<TurnResponse> + Send>;
+
+pub struct Outcome {
+    pub winner: Option<usize>,
+    /// Spaces owned by each player when the game ended
+    pub land: Vec<usize>,
+}
+
+impl Outcome {
+    /// 1 for a win, 0 for being wiped out, and otherwise the share of the board `player` held at
+    /// the end
+    pub fn score(&self, player: usize) -> f64 {
+        match self.winner {
+            Some(winner) => f64::from(u8::from(winner == player)),
+            None => {
+                let total: usize = self.land.iter().sum();
+                if total == 0 {
+                    0.0
+                } else {
+                    self.land[player] as f64 / total as f64
+                }
+            }
+        }
+    }
+}
+
+/// Plays a game the same way the server does, but calling each player directly. Invalid moves are
+/// dropped, just like the server drops them.
+pub fn play_game(players: &mut [Player], max_turns: usize) -> Result<Outcome, GenerationError> {
+    let mut state = GameState::new(players.len(), &TownConfig::default())?;
+
+    while state.turn < max_turns && state.winner().is_none() {
+        let moves = players
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(player, bot)| {
+                let response = bot(&TurnRequest {
+                    turn: state.turn,
+                    player,
+                    spaces: state.spaces,
+                })?;
+                state.validate_move(player, response.from, response.to).ok()
+            })
+            .collect();
+        if state.step(moves).is_err() {
+            state.end_turn();
+        }
+    }
+
+    let totals = player_totals(&state.spaces);
+    Ok(Outcome {
+        winner: state.winner(),
+        land: (0..players.len())
+            .map(|player| totals.get(&player).map_or(0, |t| t.land))
+            .collect(),
+    })
+}